@@ -0,0 +1,49 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+use urlpather::pattern::Pattern;
+
+const ITERATIONS: usize = 100_000;
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+fn report(label: &str, mut f: impl FnMut()) {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        f();
+    }
+    let elapsed = start.elapsed() / ITERATIONS as u32;
+    let allocations = (ALLOCATIONS.load(Ordering::Relaxed) - before) as f64 / ITERATIONS as f64;
+    println!("{label}: {elapsed:?}/match, {allocations:.1} allocations/match");
+}
+
+fn main() {
+    let pattern: Pattern = "/users/:id<number>/posts/:day<date>".parse().unwrap();
+    let path = "/users/5/posts/2021-01-01";
+
+    report("match_path", || {
+        black_box(pattern.match_path(black_box(path))).ok();
+    });
+    let mut results = Vec::new();
+    report("match_path_into", || {
+        black_box(pattern.match_path_into(black_box(path), &mut results)).ok();
+    });
+}
@@ -0,0 +1,31 @@
+use std::hint::black_box;
+use std::time::Instant;
+
+use urlpather::pattern::Pattern;
+
+const ITERATIONS: usize = 100_000;
+
+fn main() {
+    let pattern: Pattern = "/users/:id<number>/posts/:day<date>/:at<time(frac)>"
+        .parse()
+        .unwrap();
+    let path = "/users/12345/posts/2021-03-15/14:30:00.500";
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(pattern.match_path(black_box(path))).ok();
+    }
+    println!(
+        "match_path: {:?}/match",
+        start.elapsed() / ITERATIONS as u32
+    );
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        black_box(pattern.match_structure(black_box(path)));
+    }
+    println!(
+        "match_structure: {:?}/match",
+        start.elapsed() / ITERATIONS as u32
+    );
+}
@@ -0,0 +1,45 @@
+use std::collections::HashSet;
+use std::hint::black_box;
+use std::time::Instant;
+
+use urlpather::segments::{Constraints, SegType, Var};
+use urlpather::value_set::ValueSet;
+
+const VALUES: usize = 10_000;
+const ITERATIONS: usize = 100_000;
+
+fn report(label: &str, mut f: impl FnMut(&str) -> bool, inputs: &[String]) {
+    let start = Instant::now();
+    let mut hits = 0;
+    for i in 0..ITERATIONS {
+        hits += usize::from(f(black_box(&inputs[i % inputs.len()])));
+    }
+    println!(
+        "{label}: {:?}/lookup ({hits} hits)",
+        start.elapsed() / ITERATIONS as u32
+    );
+}
+
+fn main() {
+    let values: Vec<String> = (0..VALUES).map(|i| format!("product-{i:05}")).collect();
+    let inputs: Vec<String> = (0..VALUES)
+        .step_by(7)
+        .map(|i| format!("product-{:05}", i * 2))
+        .collect();
+
+    let hash_set: HashSet<&str> = values.iter().map(String::as_str).collect();
+    report("HashSet", |input| hash_set.contains(input), &inputs);
+    let value_set = ValueSet::new(&values, false);
+    report("ValueSet", |input| value_set.find(input).is_some(), &inputs);
+    let var = Var::one_of("product".to_string(), values.clone());
+    report("enum var", |input| var.try_match(input).is_ok(), &inputs);
+    // assigning `values` directly, not through `set_values`, skips the set
+    let mut constraints = Constraints::default();
+    constraints.values = values;
+    let linear = Var::with_constraints("product".to_string(), SegType::Enum, constraints);
+    report(
+        "linear enum var",
+        |input| linear.try_match(input).is_ok(),
+        &inputs,
+    );
+}
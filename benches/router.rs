@@ -0,0 +1,47 @@
+use std::hint::black_box;
+use std::time::Instant;
+
+use urlpather::pattern::Pattern;
+use urlpather::router::Router;
+
+const ROUTES: usize = 1000;
+const ITERATIONS: usize = 200;
+
+fn build() -> Router<usize> {
+    let mut router = Router::new();
+    for i in 0..ROUTES {
+        let pattern: Pattern = format!("/section{}/items/:id<number>/detail{}", i % 50, i)
+            .parse()
+            .unwrap();
+        router.add(pattern, i);
+    }
+    router
+}
+
+fn main() {
+    let paths: Vec<String> = (0..ROUTES)
+        .map(|i| format!("/section{}/items/{}/detail{}", i % 50, i, i))
+        .collect();
+    let linear = build();
+    let compiled = build().compile();
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        for path in &paths {
+            black_box(linear.match_path(black_box(path)));
+        }
+    }
+    let linear_elapsed = start.elapsed();
+
+    let start = Instant::now();
+    for _ in 0..ITERATIONS {
+        for path in &paths {
+            black_box(compiled.match_path(black_box(path)));
+        }
+    }
+    let compiled_elapsed = start.elapsed();
+
+    let per_match = |elapsed: std::time::Duration| elapsed / (ITERATIONS * ROUTES) as u32;
+    println!("linear router:   {:?}/match", per_match(linear_elapsed));
+    println!("compiled router: {:?}/match", per_match(compiled_elapsed));
+}
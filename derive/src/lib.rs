@@ -0,0 +1,96 @@
+//! Derive macros for `urlpather`; see `urlpather::seg_enum`.
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, Attribute, Data, DeriveInput, Error, Fields, LitStr};
+
+/// Implements `urlpather::seg_enum::SegEnum` for an enum of unit variants.
+#[proc_macro_derive(SegEnum, attributes(seg_enum))]
+pub fn derive_seg_enum(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: &DeriveInput) -> syn::Result<proc_macro2::TokenStream> {
+    let Data::Enum(data) = &input.data else {
+        return Err(Error::new_spanned(
+            input,
+            "SegEnum can only be derived for enums",
+        ));
+    };
+    let rename_all = attr_value(&input.attrs, "rename_all")?;
+    let mut names = Vec::new();
+    let mut variants = Vec::new();
+    for variant in &data.variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(Error::new_spanned(
+                variant,
+                "SegEnum variants can't have fields",
+            ));
+        }
+        let name = match attr_value(&variant.attrs, "rename")? {
+            Some(rename) => rename.value(),
+            None => rename_variant(&variant.ident.to_string(), rename_all.as_ref())?,
+        };
+        if names.contains(&name) {
+            return Err(Error::new_spanned(
+                variant,
+                format!("two variants are named {name:?}"),
+            ));
+        }
+        names.push(name);
+        variants.push(&variant.ident);
+    }
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics ::urlpather::seg_enum::SegEnum for #ident #ty_generics #where_clause {
+            const NAMES: &'static [&'static str] = &[#(#names),*];
+            fn from_name(name: &str) -> ::core::option::Option<Self> {
+                match name {
+                    #(#names => ::core::option::Option::Some(Self::#variants),)*
+                    _ => ::core::option::Option::None,
+                }
+            }
+        }
+    })
+}
+
+/// The string value of `#[seg_enum(key = "...")]`, if present.
+fn attr_value(attrs: &[Attribute], key: &str) -> syn::Result<Option<LitStr>> {
+    let mut value = None;
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("seg_enum")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident(key) {
+                value = Some(meta.value()?.parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("unknown seg_enum attribute"))
+            }
+        })?;
+    }
+    Ok(value)
+}
+
+fn rename_variant(ident: &str, rename_all: Option<&LitStr>) -> syn::Result<String> {
+    let Some(rule) = rename_all else {
+        return Ok(ident.to_string());
+    };
+    // split `HelloWorld` into `Hello` and `World`
+    let mut words: Vec<String> = Vec::new();
+    for c in ident.chars() {
+        match words.last_mut() {
+            Some(word) if !c.is_uppercase() => word.push(c),
+            _ => words.push(c.to_string()),
+        }
+    }
+    Ok(match rule.value().as_str() {
+        "lowercase" => ident.to_lowercase(),
+        "UPPERCASE" => ident.to_uppercase(),
+        "snake_case" => words.join("_").to_lowercase(),
+        "kebab-case" => words.join("-").to_lowercase(),
+        _ => return Err(Error::new_spanned(rule, "unknown rename_all rule")),
+    })
+}
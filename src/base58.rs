@@ -0,0 +1,40 @@
+//! Base58 decoding with the Bitcoin alphabet (see `SegType::Base58`). Each
+//! leading `1` stands for a leading zero byte; the rest is a big-endian
+//! base-58 number. No checksum is verified.
+
+use crate::segments::BASE58_ALPHABET;
+
+/// Decodes `input`, or returns `None` if it has characters outside the alphabet.
+pub fn decode(input: &str) -> Option<Vec<u8>> {
+    let zeros = input.bytes().take_while(|&b| b == b'1').count();
+    // little-endian base-256 digits of everything after the leading `1`s
+    let mut number: Vec<u8> = Vec::with_capacity(input.len());
+    for b in input.bytes().skip(zeros) {
+        let mut carry = BASE58_ALPHABET.iter().position(|&a| a == b)? as u32;
+        for digit in number.iter_mut() {
+            carry += u32::from(*digit) * 58;
+            *digit = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            number.push(carry as u8);
+            carry >>= 8;
+        }
+    }
+    let mut bytes = vec![0; zeros];
+    bytes.extend(number.iter().rev());
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn decode_base58() {
+        assert_eq!(decode("2NEpo7TZRRrLZSi2U").unwrap(), b"Hello World!");
+        assert_eq!(decode("115R").unwrap(), [0, 0, 1, 0]);
+        assert_eq!(decode("").unwrap(), b"");
+        assert!(decode("0OIl").is_none());
+    }
+}
@@ -0,0 +1,224 @@
+use std::fmt::Write;
+
+use crate::errors::BuildError;
+use crate::matches::{Capture, Matches};
+use crate::pattern::Pattern;
+use crate::segments::{format_uuid, MatchValue, SegType, Segment, Var};
+
+impl Pattern {
+    /// Builds a path (and query) this pattern matches from named values,
+    /// such as the captures of an earlier match. Each var's value is
+    /// written in a canonical form rather than as originally typed:
+    /// numbers as the shortest text that parses back to the same `f64`
+    /// (so `1.50`, `+1.5` and `1.5` all become `1.5`, and `42.0` becomes
+    /// `42`), integers without group separators, dates, times and
+    /// timestamps in ISO 8601 and UUIDs hyphenated in lowercase. Every
+    /// value is checked by matching it against its var.
+    ///
+    /// An optional var, or a group with none of its vars' values, is left
+    /// out, as is a defaulted var. Unnamed catch-alls, globs and repeated
+    /// groups can't be built.
+    pub fn build(&self, values: &Matches) -> Result<String, BuildError> {
+        let mut path = String::from("/");
+        let mut parts = Vec::new();
+        build_segments(self.segments(), values, &mut parts)?;
+        path.push_str(&parts.join("/"));
+        let mut separator = '?';
+        for param in self.query() {
+            let value = match &param.value {
+                Segment::Static(s) => s.clone(),
+                Segment::Var(var) => match present(values, var) {
+                    Some(value) => format_var(var, value, values)?,
+                    None if var.is_optional() => continue,
+                    None => return Err(missing(var)),
+                },
+                segment => return Err(unsupported(segment)),
+            };
+            let _ = write!(path, "{separator}{}={value}", param.key);
+            separator = '&';
+        }
+        Ok(path)
+    }
+}
+
+fn build_segments(
+    segments: &[Segment],
+    values: &Matches,
+    parts: &mut Vec<String>,
+) -> Result<(), BuildError> {
+    for segment in segments {
+        match segment {
+            Segment::Static(s) => parts.push(s.clone()),
+            Segment::Terminus => parts.push(String::new()),
+            Segment::Var(var) => match present(values, var) {
+                Some(value) => parts.push(format_var(var, value, values)?),
+                // optional vars only come last
+                None if var.is_optional() => break,
+                None => return Err(missing(var)),
+            },
+            Segment::Optional(group) => {
+                let any_present = group.iter().any(|segment| match segment {
+                    Segment::Var(var) => present(values, var).is_some(),
+                    _ => false,
+                });
+                if any_present {
+                    build_segments(group, values, parts)?;
+                }
+            }
+            Segment::CatchAll(Some(name), checks) => {
+                let rest = match values.get(name).and_then(Capture::value) {
+                    Some(MatchValue::String(rest)) => rest,
+                    Some(value) => {
+                        return Err(BuildError::InvalidValue {
+                            name: name.clone(),
+                            value: value.clone(),
+                        })
+                    }
+                    None => return Err(BuildError::Missing { name: name.clone() }),
+                };
+                if !rest.split('/').all(|part| checks.allows(part)) {
+                    return Err(BuildError::InvalidValue {
+                        name: name.clone(),
+                        value: MatchValue::String(rest.clone()),
+                    });
+                }
+                parts.push(rest.clone());
+            }
+            Segment::CatchAll(None, _) | Segment::Glob(_) | Segment::Repeat(_) => {
+                return Err(unsupported(segment))
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The var's value, unless it's absent or was filled from its default.
+fn present<'v>(values: &'v Matches, var: &Var) -> Option<&'v MatchValue> {
+    match values.get(&var.name)? {
+        Capture::Present(value) => Some(value),
+        Capture::Absent | Capture::Default(_) => None,
+    }
+}
+
+fn format_var(var: &Var, value: &MatchValue, values: &Matches) -> Result<String, BuildError> {
+    let constraints = var.constraints();
+    let invalid = || BuildError::InvalidValue {
+        name: var.name.clone(),
+        value: value.clone(),
+    };
+    let mut text = match (var.seg_type(), value) {
+        (SegType::Path, MatchValue::String(s)) => s.replace('%', "%25").replace('/', "%2F"),
+        (_, MatchValue::String(s)) => s.clone(),
+        // `Display` writes the shortest form that round-trips
+        (_, MatchValue::Number(n)) => n.to_string(),
+        (SegType::Month, MatchValue::Integer(month)) => usize::try_from(*month - 1)
+            .ok()
+            .and_then(|i| constraints.locale.month_names().get(i).copied())
+            .ok_or_else(invalid)?
+            .to_string(),
+        (_, MatchValue::Integer(i)) => i.to_string(),
+        (_, MatchValue::Date(d)) => d.to_string(),
+        (_, MatchValue::Time(t)) => {
+            let format = constraints.time_format.strptime_formats();
+            t.strftime(format[format.len() - 1]).to_string()
+        }
+        (_, MatchValue::Timestamp(t)) => t.to_string(),
+        (_, MatchValue::Uuid(u)) => format_uuid(*u),
+        (SegType::Bytes, MatchValue::Bytes(bytes)) => {
+            bytes.iter().map(|b| format!("{b:02x}")).collect()
+        }
+        _ => return Err(invalid()),
+    };
+    if let Some(prefix) = &constraints.strip_prefix {
+        text.insert_str(0, prefix);
+    }
+    if !constraints.units.is_empty() {
+        let unit = format!("{}.unit", var.name);
+        match values.value(&unit) {
+            Some(MatchValue::String(unit)) => text.push_str(unit),
+            _ => return Err(BuildError::Missing { name: unit }),
+        }
+    }
+    if var.try_match(&text).is_err() || text.contains(['/', '?', '&', '#']) {
+        return Err(invalid());
+    }
+    Ok(text)
+}
+
+fn missing(var: &Var) -> BuildError {
+    BuildError::Missing {
+        name: var.name.clone(),
+    }
+}
+
+fn unsupported(segment: &Segment) -> BuildError {
+    BuildError::Unsupported {
+        segment: segment.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn number(n: &str) -> Result<String, BuildError> {
+        let pattern: Pattern = "/price/:p<number>".parse().unwrap();
+        let matches = pattern.captures(&format!("/price/{n}")).unwrap();
+        pattern.build(&matches)
+    }
+
+    #[test]
+    fn build_canonical_numbers() {
+        for n in ["1.5", "1.50", "+1.5", "01.5", "15e-1"] {
+            assert_eq!(number(n).unwrap(), "/price/1.5", "{n}");
+        }
+        assert_eq!(number("42.0").unwrap(), "/price/42");
+        assert_eq!(number("-0.25").unwrap(), "/price/-0.25");
+    }
+    #[test]
+    fn build_round_trips_captures() {
+        let pattern: Pattern =
+            "/users/:id<integer(sep=comma)>/posts/:day<date>/(:page<integer>)?/?sort=:s&v=2"
+                .parse()
+                .unwrap();
+        let matches = pattern
+            .captures("/users/1,000/posts/2021-01-02/3/?sort=new&v=2")
+            .unwrap();
+        assert_eq!(
+            pattern.build(&matches).unwrap(),
+            "/users/1000/posts/2021-01-02/3/?sort=new&v=2"
+        );
+        let matches = pattern
+            .captures("/users/7/posts/2021-01-02/?sort=new&v=2")
+            .unwrap();
+        assert_eq!(
+            pattern.build(&matches).unwrap(),
+            "/users/7/posts/2021-01-02/?sort=new&v=2"
+        );
+    }
+    #[test]
+    fn build_errors() {
+        let pattern: Pattern = "/users/:id<integer(1..10)>".parse().unwrap();
+        let values: Matches = [("id".to_string(), MatchValue::Integer(20))]
+            .into_iter()
+            .collect();
+        assert_eq!(
+            pattern.build(&values),
+            Err(BuildError::InvalidValue {
+                name: "id".to_string(),
+                value: MatchValue::Integer(20)
+            })
+        );
+        assert_eq!(
+            pattern.build(&Matches::default()),
+            Err(BuildError::Missing {
+                name: "id".to_string()
+            })
+        );
+        let pattern: Pattern = "/static/*.css".parse().unwrap();
+        assert!(matches!(
+            pattern.build(&Matches::default()),
+            Err(BuildError::Unsupported { .. })
+        ));
+    }
+}
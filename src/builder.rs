@@ -0,0 +1,306 @@
+//! A fluent way to assemble a `Pattern` without parsing one, as in
+//! `Pattern::builder().segment("users").var_integer("id").range(1..=1000)`.
+//! Each typed var returns a sub-builder offering only the constraints its
+//! type supports, so a misplaced constraint is a compile error rather than
+//! a `ParserConfigError`. What the types can't rule out, like a static with
+//! a `/` in it, a range whose min is above its max or an optional var that
+//! isn't last, `build` checks as the parser would.
+
+use std::marker::PhantomData;
+use std::ops::RangeInclusive;
+
+use crate::config::{check_bounds, check_segments, is_plain_static, is_valid_name};
+use crate::errors::ParserConfigError;
+use crate::pattern::Pattern;
+use crate::segments::{SegType, Segment, Var};
+
+impl Pattern {
+    /// Starts a `PatternBuilder`.
+    pub fn builder() -> PatternBuilder {
+        PatternBuilder::default()
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PatternBuilder {
+    segments: Vec<Segment>,
+}
+
+impl PatternBuilder {
+    /// Appends a static segment.
+    pub fn segment(mut self, segment: impl Into<String>) -> Self {
+        self.segments.push(Segment::Static(segment.into()));
+        self
+    }
+    /// Appends a var built some other way, such as `Var::one_of`.
+    pub fn var(mut self, var: Var) -> Self {
+        self.segments.push(Segment::Var(var));
+        self
+    }
+    pub fn var_number(self, name: impl Into<String>) -> VarBuilder<NumberVar> {
+        VarBuilder::new(self, name.into(), SegType::Number)
+    }
+    pub fn var_integer(self, name: impl Into<String>) -> VarBuilder<IntegerVar> {
+        VarBuilder::new(self, name.into(), SegType::Integer)
+    }
+    pub fn var_string(self, name: impl Into<String>) -> VarBuilder<StringVar> {
+        VarBuilder::new(self, name.into(), SegType::String)
+    }
+    /// Checks the pattern as `Pattern::from_str` would check its string
+    /// form, then builds it.
+    pub fn build(self) -> Result<Pattern, ParserConfigError> {
+        for segment in &self.segments {
+            match segment {
+                Segment::Static(s) if !is_plain_static(s) => {
+                    return Err(ParserConfigError::InvalidStatic)
+                }
+                Segment::Var(var) => check_var(var)?,
+                _ => {}
+            }
+        }
+        check_segments(&self.segments)?;
+        Ok(Pattern::new(self.segments))
+    }
+}
+
+fn check_var(var: &Var) -> Result<(), ParserConfigError> {
+    if !is_valid_name(&var.name) {
+        return Err(ParserConfigError::InvalidVar);
+    }
+    let c = var.constraints();
+    check_order(c.int_min, c.int_max)?;
+    check_order(c.num_min, c.num_max)?;
+    check_order(c.min_len, c.max_len)
+}
+
+/// A var may have no bounds at all, but set ones must be ordered.
+fn check_order<N: PartialOrd>(min: Option<N>, max: Option<N>) -> Result<(), ParserConfigError> {
+    if min.is_some() || max.is_some() {
+        check_bounds(min, max)?;
+    }
+    Ok(())
+}
+
+/// Marks a `VarBuilder` for a `SegType::Number` var.
+#[derive(Debug, Clone, Copy)]
+pub enum NumberVar {}
+/// Marks a `VarBuilder` for a `SegType::Integer` var.
+#[derive(Debug, Clone, Copy)]
+pub enum IntegerVar {}
+/// Marks a `VarBuilder` for a `SegType::String` var.
+#[derive(Debug, Clone, Copy)]
+pub enum StringVar {}
+
+/// Sets a var's constraints, then carries on with the pattern: appending
+/// another segment or building finishes the var.
+#[derive(Debug, Clone)]
+pub struct VarBuilder<T> {
+    pattern: PatternBuilder,
+    var: Var,
+    seg_type: PhantomData<T>,
+}
+
+impl<T> VarBuilder<T> {
+    fn new(pattern: PatternBuilder, name: String, seg_type: SegType) -> Self {
+        Self {
+            pattern,
+            var: Var::new(name, seg_type),
+            seg_type: PhantomData,
+        }
+    }
+    /// See `Var::optional`.
+    pub fn optional(mut self) -> Self {
+        self.var = self.var.optional();
+        self
+    }
+    /// See `Var::with_default`.
+    pub fn with_default(mut self, default: impl Into<String>) -> Self {
+        self.var = self.var.with_default(default);
+        self
+    }
+    /// Finishes the var, returning to the pattern.
+    pub fn done(self) -> PatternBuilder {
+        self.pattern.var(self.var)
+    }
+    pub fn segment(self, segment: impl Into<String>) -> PatternBuilder {
+        self.done().segment(segment)
+    }
+    pub fn var(self, var: Var) -> PatternBuilder {
+        self.done().var(var)
+    }
+    pub fn var_number(self, name: impl Into<String>) -> VarBuilder<NumberVar> {
+        self.done().var_number(name)
+    }
+    pub fn var_integer(self, name: impl Into<String>) -> VarBuilder<IntegerVar> {
+        self.done().var_integer(name)
+    }
+    pub fn var_string(self, name: impl Into<String>) -> VarBuilder<StringVar> {
+        self.done().var_string(name)
+    }
+    pub fn build(self) -> Result<Pattern, ParserConfigError> {
+        self.done().build()
+    }
+}
+
+impl VarBuilder<NumberVar> {
+    /// Bounds the value, inclusively.
+    pub fn range<N: Into<f64>>(self, range: RangeInclusive<N>) -> Self {
+        let (min, max) = range.into_inner();
+        self.min(min).max(max)
+    }
+    pub fn min(mut self, min: impl Into<f64>) -> Self {
+        self.var.constraints_mut().num_min = Some(min.into());
+        self
+    }
+    pub fn max(mut self, max: impl Into<f64>) -> Self {
+        self.var.constraints_mut().num_max = Some(max.into());
+        self
+    }
+    /// Also accepts `inf` and `nan`; see `Constraints::special`.
+    pub fn special(mut self) -> Self {
+        self.var.constraints_mut().special = true;
+        self
+    }
+}
+
+impl VarBuilder<IntegerVar> {
+    /// Bounds the value, inclusively.
+    pub fn range<N: Into<i64>>(self, range: RangeInclusive<N>) -> Self {
+        let (min, max) = range.into_inner();
+        self.min(min).max(max)
+    }
+    pub fn min(mut self, min: impl Into<i64>) -> Self {
+        self.var.constraints_mut().int_min = Some(min.into());
+        self
+    }
+    pub fn max(mut self, max: impl Into<i64>) -> Self {
+        self.var.constraints_mut().int_max = Some(max.into());
+        self
+    }
+    /// Clamps an out-of-range value to the nearest bound instead of
+    /// rejecting it.
+    pub fn clamp(mut self) -> Self {
+        self.var.constraints_mut().clamp = true;
+        self
+    }
+}
+
+impl VarBuilder<StringVar> {
+    /// Bounds the length in chars, inclusively.
+    pub fn min_len(mut self, min: usize) -> Self {
+        self.var.constraints_mut().min_len = Some(min);
+        self
+    }
+    pub fn max_len(mut self, max: usize) -> Self {
+        self.var.constraints_mut().max_len = Some(max);
+        self
+    }
+    /// Rejects values with uppercase letters.
+    pub fn lowercased(mut self) -> Self {
+        self.var.constraints_mut().lowercase = true;
+        self
+    }
+    /// Rejects `word` as a value, as `string(not=..)` does.
+    pub fn excluding(mut self, word: impl Into<String>) -> Self {
+        self.var.constraints_mut().excluded.push(word.into());
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::errors::MatchError;
+    use crate::segments::MatchValue;
+
+    #[test]
+    fn builder_number_range() {
+        let pattern = Pattern::builder()
+            .segment("items")
+            .var_number("n")
+            .range(1..=100)
+            .build()
+            .unwrap();
+        assert_eq!(
+            pattern.captures("/items/42.5").unwrap().value("n"),
+            Some(&MatchValue::Number(42.5))
+        );
+        assert_eq!(
+            pattern.match_path("/items/101"),
+            Err(MatchError::NumberOutOfRange {
+                min: Some(1.0),
+                max: Some(100.0),
+                got: 101.0
+            })
+        );
+        // the built pattern round-trips through its string form
+        let parsed: Pattern = pattern.to_string().parse().unwrap();
+        assert_eq!(parsed, pattern);
+    }
+    #[test]
+    fn builder_string_constraints() {
+        let pattern = Pattern::builder()
+            .var_string("s")
+            .max_len(5)
+            .lowercased()
+            .var_integer("page")
+            .optional()
+            .build()
+            .unwrap();
+        assert_eq!(
+            pattern.to_string(),
+            "/:s<string(len=..=5,lowercase)>/:page<integer>?"
+        );
+        assert!(pattern.match_path("/abc").is_ok());
+        assert_eq!(
+            pattern.match_path("/aBc"),
+            Err(MatchError::NotLowercase {
+                got: "aBc".to_string()
+            })
+        );
+        assert!(pattern.match_path("/abcdef").is_err());
+    }
+    #[test]
+    fn builder_rejects_what_the_parser_would() {
+        assert!(matches!(
+            Pattern::builder().segment("a/b").build(),
+            Err(ParserConfigError::InvalidStatic)
+        ));
+        // a static the parser would read as a glob
+        assert!(matches!(
+            Pattern::builder().segment("*.css").build(),
+            Err(ParserConfigError::InvalidStatic)
+        ));
+        assert!(matches!(
+            Pattern::builder().var_string("a-b").build(),
+            Err(ParserConfigError::InvalidVar)
+        ));
+        assert!(matches!(
+            Pattern::builder()
+                .var_integer("page")
+                .optional()
+                .segment("x")
+                .build(),
+            Err(ParserConfigError::InvalidOptional)
+        ));
+        assert!(matches!(
+            Pattern::builder()
+                .var_integer("n")
+                .range(RangeInclusive::new(10, 1))
+                .build(),
+            Err(ParserConfigError::InvalidModifier)
+        ));
+        assert!(matches!(
+            Pattern::builder().var_number("x").min(2).max(1).build(),
+            Err(ParserConfigError::InvalidModifier)
+        ));
+        assert!(matches!(
+            Pattern::builder()
+                .var_string("s")
+                .min_len(3)
+                .max_len(2)
+                .build(),
+            Err(ParserConfigError::InvalidModifier)
+        ));
+    }
+}
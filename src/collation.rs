@@ -0,0 +1,75 @@
+//! Primary-strength comparison for `enum.collate(..)` values, in the
+//! spirit of the Unicode Collation Algorithm's root order: case and
+//! accents are ignored, so `Café`, `CAFE` and `cafe` are equal. Letters
+//! that decompose canonically into a base letter and marks fold to the
+//! base, combining marks are dropped, and `ß`, `æ` and `œ` expand to `ss`,
+//! `ae` and `oe`.
+//!
+//! The table is in-crate and only covers Latin-1, Latin Extended-A and a
+//! few letters beyond; other scripts are compared by their lowercase
+//! forms alone, so Greek or Vietnamese accents still count. There's no
+//! locale tailoring either: Swedish `å` and Turkish dotless `ı` are
+//! treated as their root forms rather than as separate letters.
+
+/// The key `s` is compared by: equal keys collate equally.
+pub fn collation_key(s: &str) -> String {
+    let mut key = String::with_capacity(s.len());
+    for c in s.chars().flat_map(char::to_lowercase) {
+        if is_combining_mark(c) {
+            continue;
+        }
+        match fold(c) {
+            Some(folded) => key.push_str(folded),
+            None => key.push(c),
+        }
+    }
+    key
+}
+
+fn is_combining_mark(c: char) -> bool {
+    matches!(c, '\u{300}'..='\u{36f}')
+}
+
+/// The base letters of a lowercase letter with diacritics.
+fn fold(c: char) -> Option<&'static str> {
+    Some(match c {
+        'à'..='å' | 'ā' | 'ă' | 'ą' | 'ǎ' => "a",
+        'æ' => "ae",
+        'ç' | 'ć' | 'ĉ' | 'ċ' | 'č' => "c",
+        'ď' => "d",
+        'è'..='ë' | 'ē' | 'ĕ' | 'ė' | 'ę' | 'ě' => "e",
+        'ĝ' | 'ğ' | 'ġ' | 'ģ' => "g",
+        'ĥ' => "h",
+        'ì'..='ï' | 'ĩ' | 'ī' | 'ĭ' | 'į' | 'ǐ' => "i",
+        'ĵ' => "j",
+        'ķ' => "k",
+        'ĺ' | 'ļ' | 'ľ' => "l",
+        'ñ' | 'ń' | 'ņ' | 'ň' => "n",
+        'ò'..='ö' | 'ō' | 'ŏ' | 'ő' | 'ǒ' => "o",
+        'œ' => "oe",
+        'ŕ' | 'ŗ' | 'ř' => "r",
+        'ś' | 'ŝ' | 'ş' | 'š' | 'ș' => "s",
+        'ß' => "ss",
+        'ţ' | 'ť' | 'ț' => "t",
+        'ù'..='ü' | 'ũ' | 'ū' | 'ŭ' | 'ů' | 'ű' | 'ų' | 'ǔ' => "u",
+        'ŵ' => "w",
+        'ý' | 'ÿ' | 'ŷ' => "y",
+        'ź' | 'ż' | 'ž' => "z",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn collation_key_ignores_case_and_accents() {
+        assert_eq!(collation_key("Café"), "cafe");
+        // `e` followed by a combining acute accent
+        assert_eq!(collation_key("cafe\u{301}"), "cafe");
+        assert_eq!(collation_key("Straße"), "strasse");
+        assert_eq!(collation_key("ŒUVRE"), "oeuvre");
+        assert_eq!(collation_key("Ελλάδα"), "ελλάδα");
+    }
+}
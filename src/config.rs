@@ -1,8 +1,424 @@
+use std::borrow::Cow;
+use std::str::FromStr;
+
 use crate::errors::ParserConfigError;
-use crate::segments::SegType;
+use crate::pattern::{Pattern, QueryParam};
+use crate::segments::{Constraints, RestChecks, SegType, Segment, TimeFormat, Var};
+
+/// Patterns look like `/users/:id<number>/posts/:slug<string(max_bytes=64)>`.
+/// A var is `:name`, optionally followed by `<type>` or `<type(modifiers)>`,
+/// where modifiers are comma-separated `key=value` pairs. A trailing `/`
+/// becomes a `Segment::Terminus`, as does a final `$` segment, which
+/// spells out that nothing may follow: `/users/:id/$` matches `/users/5`
+/// and `/users/5/` but not `/users/5/extra`. A `$` segment anywhere else
+/// is an error. `(seg/seg)*` is a repeated group, which
+/// may only be followed by a terminus. `:name?` is an optional var and
+/// `:name<type=default>` an optional var with a default; optional vars may
+/// only be followed by other optional vars. `(seg/seg)?` is an optional
+/// group, matched all or nothing anywhere in the pattern; it can also be
+/// written with the slash inside, as in `/api/(v:version<number>/)?users`.
+/// Since each group doubles the worst-case matching work, a pattern may
+/// have at most `MAX_OPTIONAL_GROUPS` of them.
+///
+/// `number|date` tries each type in turn, recording the one that matched in
+/// `MatchResult::Alternative`; alternations don't take modifiers.
+///
+/// `string(not=new,edit)` rejects the listed words. Since `not=` takes every
+/// following modifier without an `=`, flags have to come before it.
+/// `string(=32)`, for tokens of a known length, is shorthand for
+/// `string(len=32)`: exactly 32 chars.
+///
+/// `**` (or `**name`, to capture it) is a catch-all taking any number of
+/// segments, which may only be followed by statics, globs and required
+/// vars; a static containing `*`, like `*.css`, is a glob. `**name(no_dots)`
+/// rejects `.` and `..` in the captured rest, and `no_empty` rejects empty
+/// parts, both useful when the rest is treated as a file path.
+///
+/// A trailing `?key=value&key=value` declares query params, matched in any
+/// order, where each value is a var or a static: `/search?q=:q&page=:p<number=1>`.
+/// A var that's optional or has a default may be left out of the query,
+/// and undeclared params are ignored unless the pattern is given
+/// `ExtraParams::Reject`.
+///
+/// A var may be preceded by a static prefix, as in `v:version<number>`,
+/// which is shorthand for the `strip_prefix=v` modifier.
+///
+/// Leading and trailing whitespace is ignored, as is a trailing comment
+/// starting with `#`, which must be separated from the pattern by
+/// whitespace: `/users/:id<number>  # user detail`. Whitespace anywhere
+/// else, or a `#` directly touching the pattern, is an error.
+impl FromStr for Pattern {
+    type Err = ParserConfigError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        ParseOptions::default().parse(s)
+    }
+}
+
+/// Settings for parsing patterns that `FromStr` leaves at their defaults.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// The type of a var written without one, like `:name` or `:name<=x>`.
+    /// `None` makes untyped vars an error. Defaults to `SegType::String`.
+    pub untyped: Option<SegType>,
+}
+
+impl Pattern {
+    /// Parses a host pattern like `api.*.example.com` or
+    /// `:tenant.example.com`, whose `.`-separated labels take the place of
+    /// path segments. Match hosts against it with `Pattern::match_host`.
+    /// Each label is a static, a var or a glob: groups, catch-alls, `$`
+    /// and query params are path-only.
+    pub fn parse_host(s: &str) -> Result<Pattern, ParserConfigError> {
+        if s.is_empty() || s.contains('/') {
+            return Err(ParserConfigError::InvalidHost);
+        }
+        let pattern: Pattern = format!("/{}", split_top_level(s, '.').join("/")).parse()?;
+        let labels = pattern.segments().iter().all(|segment| {
+            matches!(
+                segment,
+                Segment::Static(_) | Segment::Var(_) | Segment::Glob(_)
+            )
+        });
+        if !labels || !pattern.query().is_empty() {
+            return Err(ParserConfigError::InvalidHost);
+        }
+        Ok(pattern)
+    }
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        Self {
+            untyped: Some(SegType::String),
+        }
+    }
+}
+
+impl ParseOptions {
+    /// Parses `s` as `Pattern::from_str` does, but with these options.
+    pub fn parse(&self, s: &str) -> Result<Pattern, ParserConfigError> {
+        let s = strip_comment(s)?;
+        let (s, query) = split_query_spec(s);
+        let query = query
+            .map(|query| parse_query(query, self))
+            .transpose()?
+            .unwrap_or_default();
+        let body = s.strip_prefix('/').unwrap_or(s);
+        let parts = split_segments(body);
+        let last = parts.len() - 1;
+        let segments = parts
+            .iter()
+            .enumerate()
+            .map(|(i, part)| parse_segment(part, i == last, self))
+            .collect::<Result<Vec<_>, _>>()?;
+        check_segments(&segments)?;
+        Ok(Pattern::new(segments).with_query(query))
+    }
+}
+
+/// Checks where segments may appear: nothing but a terminus after a repeat,
+/// only single parts after a catch-all, at most `MAX_OPTIONAL_GROUPS`
+/// optional groups, and optional segments last.
+pub(crate) fn check_segments(segments: &[Segment]) -> Result<(), ParserConfigError> {
+    if let Some(i) = segments
+        .iter()
+        .position(|s| matches!(s, Segment::Repeat(_)))
+    {
+        if !segments[i + 1..].iter().all(|s| *s == Segment::Terminus) {
+            return Err(ParserConfigError::InvalidGroup);
+        }
+    }
+    if let Some(i) = segments
+        .iter()
+        .position(|s| matches!(s, Segment::CatchAll(..)))
+    {
+        // everything after a catch-all must take exactly one part
+        if !segments[i + 1..].iter().all(|s| {
+            matches!(s, Segment::Static(_) | Segment::Glob(_))
+                || matches!(s, Segment::Var(v) if !v.is_optional())
+        }) {
+            return Err(ParserConfigError::InvalidGroup);
+        }
+    }
+    let groups = segments
+        .iter()
+        .filter(|s| matches!(s, Segment::Optional(_)))
+        .count();
+    if groups > MAX_OPTIONAL_GROUPS {
+        return Err(ParserConfigError::InvalidGroup);
+    }
+    if let Some(i) = segments.iter().position(Segment::is_optional) {
+        if !segments[i..].iter().all(Segment::is_optional) {
+            return Err(ParserConfigError::InvalidOptional);
+        }
+    }
+    Ok(())
+}
+
+/// The `Pattern::specificity` of `source`, read from its shape alone so
+/// routes can be ordered without parsing them. Only meaningful for sources
+/// that parse.
+pub(crate) fn source_specificity(source: &str) -> Vec<u8> {
+    let s = strip_comment(source).unwrap_or(source);
+    let (s, _) = split_query_spec(s);
+    split_segments(s.strip_prefix('/').unwrap_or(s))
+        .iter()
+        .map(|part| {
+            if part.starts_with(':') {
+                1
+            } else if part.starts_with('(') && (part.ends_with(")*") || part.ends_with(")?")) {
+                0
+            } else if part.contains(':') {
+                1
+            } else if part.starts_with("**") {
+                0
+            } else if part.contains('*') {
+                1
+            } else {
+                2
+            }
+        })
+        .chain(std::iter::once(3))
+        .collect()
+}
+
+/// The most optional groups a single pattern may contain.
+pub const MAX_OPTIONAL_GROUPS: usize = 4;
+
+fn strip_comment(s: &str) -> Result<&str, ParserConfigError> {
+    let s = s.trim_start();
+    let pattern = match s.find('#') {
+        Some(0) => return Err(ParserConfigError::EmptyPattern),
+        Some(i) if !s[..i].ends_with(char::is_whitespace) => {
+            return Err(ParserConfigError::AmbiguousComment)
+        }
+        Some(i) => &s[..i],
+        None => s,
+    }
+    .trim_end();
+    if pattern.is_empty() {
+        Err(ParserConfigError::EmptyPattern)
+    } else if pattern.contains(char::is_whitespace) {
+        Err(ParserConfigError::UnexpectedWhitespace)
+    } else {
+        Ok(pattern)
+    }
+}
+
+/// Splits a pattern body into its segments, moving the slash of an optional
+/// group written as `(a/)?b` out of it, as in `(a)?/b`.
+fn split_segments(body: &str) -> Vec<Cow<'_, str>> {
+    let mut parts = Vec::new();
+    for part in split_top_level(body, '/') {
+        match slash_group(part) {
+            Some((group, rest)) => {
+                parts.push(Cow::Owned(format!("{group})?")));
+                parts.push(Cow::Borrowed(rest));
+            }
+            None => parts.push(Cow::Borrowed(part)),
+        }
+    }
+    parts
+}
+
+/// For a part like `(a/)?b`, the group up to its trailing slash (`(a`)
+/// and what follows the group (`b`).
+fn slash_group(part: &str) -> Option<(&str, &str)> {
+    if !part.starts_with('(') {
+        return None;
+    }
+    let mut depth = 0usize;
+    for (i, c) in part.char_indices() {
+        match c {
+            '(' | '<' => depth += 1,
+            ')' | '>' => {
+                depth = depth.saturating_sub(1);
+                if depth == 0 {
+                    let rest = part[i + 1..].strip_prefix('?')?;
+                    return Some((part[..i].strip_suffix('/')?, rest));
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Splits on `separator`s that aren't nested inside `(...)` or `<...>`.
+fn split_top_level(body: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0usize;
+    let mut start = 0;
+    for (i, c) in body.char_indices() {
+        match c {
+            '(' | '<' => depth += 1,
+            ')' | '>' => depth = depth.saturating_sub(1),
+            c if c == separator && depth == 0 => {
+                parts.push(&body[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&body[start..]);
+    parts
+}
+
+/// Splits off a trailing query spec. A `?` also marks optional vars and
+/// groups, so only one followed by `key=` starts the query.
+fn split_query_spec(s: &str) -> (&str, Option<&str>) {
+    s.match_indices('?')
+        .find_map(|(i, _)| {
+            let rest = &s[i + 1..];
+            let (key, _) = rest.split_once('=')?;
+            (is_valid_static(key) && !key.contains('&')).then_some((&s[..i], Some(rest)))
+        })
+        .unwrap_or((s, None))
+}
+
+fn parse_query(query: &str, options: &ParseOptions) -> Result<Vec<QueryParam>, ParserConfigError> {
+    let mut params: Vec<QueryParam> = Vec::new();
+    for pair in query.split('&') {
+        let (key, value) = pair
+            .split_once('=')
+            .ok_or(ParserConfigError::InvalidQuery)?;
+        if !is_valid_static(key) || params.iter().any(|param| param.key == key) {
+            return Err(ParserConfigError::InvalidQuery);
+        }
+        let value = match value.strip_prefix(':') {
+            Some(var) => Segment::Var(parse_var(var, options)?),
+            None if is_valid_static(value) => Segment::Static(value.to_string()),
+            None => return Err(ParserConfigError::InvalidQuery),
+        };
+        params.push(QueryParam {
+            key: key.to_string(),
+            value,
+        });
+    }
+    Ok(params)
+}
 
-/// TODO(SHR): Replace this with real parsing after we settle on format
-/// in the meantime, notes:
+fn parse_segment(
+    part: &str,
+    is_last: bool,
+    options: &ParseOptions,
+) -> Result<Segment, ParserConfigError> {
+    if let Some(var) = part.strip_prefix(':') {
+        parse_var(var, options).map(Segment::Var)
+    } else if let Some(group) = part.strip_prefix('(').and_then(|p| p.strip_suffix(")*")) {
+        parse_group(group, options).map(Segment::Repeat)
+    } else if let Some(group) = part.strip_prefix('(').and_then(|p| p.strip_suffix(")?")) {
+        parse_group(group, options).map(Segment::Optional)
+    } else if let Some((prefix, var)) = part.split_once(':') {
+        parse_prefixed_var(prefix, var, options)
+    } else if let Some(rest) = part.strip_prefix("**") {
+        parse_catch_all(rest)
+    } else if part.is_empty() && is_last {
+        Ok(Segment::Terminus)
+    } else if part == "$" {
+        is_last
+            .then_some(Segment::Terminus)
+            .ok_or(ParserConfigError::MisplacedTerminus)
+    } else if part.contains('*') && is_valid_static(part) {
+        Ok(Segment::Glob(part.to_string()))
+    } else if is_valid_static(part) {
+        Ok(Segment::Static(part.to_string()))
+    } else {
+        Err(ParserConfigError::InvalidStatic)
+    }
+}
+
+/// `name(no_dots,no_empty)` after the `**`, every part optional.
+fn parse_catch_all(rest: &str) -> Result<Segment, ParserConfigError> {
+    let (name, modifiers) = match rest.split_once('(') {
+        Some((name, modifiers)) => (
+            name,
+            modifiers
+                .strip_suffix(')')
+                .ok_or(ParserConfigError::InvalidModifier)?,
+        ),
+        None => (rest, ""),
+    };
+    let name = match name {
+        "" => None,
+        name if is_valid_name(name) => Some(name.to_string()),
+        _ => return Err(ParserConfigError::InvalidVar),
+    };
+    let mut checks = RestChecks::default();
+    if rest.contains('(') {
+        for modifier in modifiers.split(',') {
+            match modifier.trim() {
+                "no_dots" => checks.no_dots = true,
+                "no_empty" => checks.no_empty = true,
+                _ => return Err(ParserConfigError::InvalidModifier),
+            }
+        }
+    }
+    Ok(Segment::CatchAll(name, checks))
+}
+
+fn parse_group(group: &str, options: &ParseOptions) -> Result<Vec<Segment>, ParserConfigError> {
+    let segments = split_top_level(group, '/')
+        .iter()
+        .map(|part| parse_segment(part, false, options))
+        .collect::<Result<Vec<_>, _>>()?;
+    if segments.is_empty()
+        || segments.iter().any(|s| {
+            matches!(
+                s,
+                Segment::Repeat(_) | Segment::Optional(_) | Segment::CatchAll(..)
+            ) || s.is_optional()
+        })
+    {
+        return Err(ParserConfigError::InvalidGroup);
+    }
+    Ok(segments)
+}
+
+fn parse_prefixed_var(
+    prefix: &str,
+    var: &str,
+    options: &ParseOptions,
+) -> Result<Segment, ParserConfigError> {
+    if !is_valid_static(prefix) {
+        return Err(ParserConfigError::InvalidStatic);
+    }
+    let mut var = parse_var(var, options)?;
+    // enum and template modifiers can't carry a `strip_prefix` to display
+    if matches!(var.seg_type(), SegType::Enum | SegType::Template)
+        || var.constraints().strip_prefix.is_some()
+    {
+        return Err(ParserConfigError::InvalidVar);
+    }
+    // the default was checked without the prefix
+    if var.default().is_some() {
+        return Err(ParserConfigError::InvalidDefault);
+    }
+    var.constraints_mut().strip_prefix = Some(prefix.to_string());
+    Ok(Segment::Var(var))
+}
+
+pub(crate) fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Whether the parser reads `part` back as the static segment `part`,
+/// rather than as a glob, a group or a terminus.
+pub(crate) fn is_plain_static(part: &str) -> bool {
+    is_valid_static(part)
+        && matches!(
+            parse_segment(part, false, &ParseOptions::default()),
+            Ok(Segment::Static(parsed)) if parsed == part
+        )
+}
+
+// static segments can't contain / or other url-invalid chars
+pub(crate) fn is_valid_static(part: &str) -> bool {
+    !part.is_empty()
+        && part
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-._~!$&'()*+,;=@".contains(c))
+}
 
 impl TryFrom<&str> for SegType {
     type Error = ParserConfigError;
@@ -10,17 +426,765 @@ impl TryFrom<&str> for SegType {
         match value {
             "" => Ok(SegType::String),
             "number" => Ok(SegType::Number),
+            "integer" => Ok(SegType::Integer),
             "string" => Ok(SegType::String),
             "date" => Ok(SegType::Date),
+            "time" => Ok(SegType::Time),
+            "uuid" => Ok(SegType::Uuid),
+            "template" => Ok(SegType::Template),
+            "enum" => Ok(SegType::Enum),
+            "month" => Ok(SegType::Month),
+            "bytes" => Ok(SegType::Bytes),
+            "base58" => Ok(SegType::Base58),
+            "timestamp" => Ok(SegType::Timestamp),
+            "path" => Ok(SegType::Path),
+            "jwt" => Ok(SegType::Jwt),
             _ => Err(ParserConfigError::InvalidSegmentType),
         }
     }
 }
 
-// static segments can't contain / or other url-invalid chars
+/// Splits a `type(modifiers)=default` spec at the first `=` outside the
+/// modifier list.
+fn split_default(spec: &str) -> (&str, Option<&str>) {
+    let mut depth = 0usize;
+    for (i, c) in spec.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth = depth.saturating_sub(1),
+            '=' if depth == 0 => return (&spec[..i], Some(&spec[i + 1..])),
+            _ => {}
+        }
+    }
+    (spec, None)
+}
 
-// impl TryFrom<&str> for Var {
-//     type Error = ParserConfigError;
-//
-//     fn try_from(value: &str) -> Result<Self, Self::Error> {}
-// }
+fn parse_type_spec(
+    spec: &str,
+    options: &ParseOptions,
+) -> Result<(SegType, Constraints), ParserConfigError> {
+    let (type_name, modifiers) = match spec.split_once('(') {
+        Some((name, rest)) => (
+            name,
+            Some(
+                rest.strip_suffix(')')
+                    .ok_or(ParserConfigError::InvalidModifier)?,
+            ),
+        ),
+        None => (spec, None),
+    };
+    let (type_name, case_insensitive) = match type_name.strip_suffix(".i") {
+        Some(base) => (base, true),
+        None => (type_name, false),
+    };
+    #[cfg(feature = "collation")]
+    let (type_name, collate) = match type_name.strip_suffix(".collate") {
+        Some(base) => (base, true),
+        None => (type_name, false),
+    };
+    #[cfg(not(feature = "collation"))]
+    let collate = false;
+    let type_name = match type_name {
+        "" => options
+            .untyped
+            .ok_or(ParserConfigError::UntypedVar)?
+            .as_str(),
+        type_name => type_name,
+    };
+    let mut types = type_name.split('|');
+    let seg_type = SegType::try_from(types.next().unwrap_or_default())?;
+    let alternatives = types
+        .map(SegType::try_from)
+        .collect::<Result<Vec<_>, _>>()?;
+    if !alternatives.is_empty() {
+        if type_name.split('|').any(str::is_empty) {
+            return Err(ParserConfigError::InvalidSegmentType);
+        }
+        // modifiers would be ambiguous about which type they belong to
+        if modifiers.is_some() || case_insensitive || collate {
+            return Err(ParserConfigError::InvalidModifier);
+        }
+        let all = || std::iter::once(&seg_type).chain(&alternatives);
+        if all().any(|t| matches!(t, SegType::Enum | SegType::Template | SegType::Bytes))
+            || all()
+                .enumerate()
+                .any(|(i, t)| all().skip(i + 1).any(|u| u == t))
+        {
+            return Err(ParserConfigError::InvalidSegmentType);
+        }
+    }
+    let mut constraints = Constraints {
+        case_insensitive,
+        alternatives,
+        ..Default::default()
+    };
+    if (case_insensitive || collate) && seg_type != SegType::Enum {
+        return Err(ParserConfigError::InvalidModifier);
+    }
+    #[cfg(feature = "collation")]
+    {
+        constraints.collate = collate;
+    }
+    if seg_type == SegType::Enum {
+        // the arguments are the values themselves
+        let values: Vec<String> = modifiers
+            .into_iter()
+            .flat_map(|m| m.split(','))
+            .map(|v| v.trim().to_string())
+            .collect();
+        if values.is_empty() || values.iter().any(String::is_empty) {
+            return Err(ParserConfigError::InvalidModifier);
+        }
+        constraints.set_values(values);
+        return Ok((seg_type, constraints));
+    }
+    if seg_type == SegType::Template {
+        // the whole argument is the template, commas and all
+        let template = modifiers.ok_or(ParserConfigError::InvalidTemplate)?;
+        constraints.template = Some(template.parse()?);
+        return Ok((seg_type, constraints));
+    }
+    if let Some(modifiers) = modifiers {
+        let mut excluding = false;
+        for modifier in modifiers.split(',').map(str::trim) {
+            // `not=` takes every following value up to the next `key=value`
+            if excluding && !modifier.contains('=') {
+                push_excluded(&mut constraints, modifier)?;
+                continue;
+            }
+            excluding = false;
+            match modifier.strip_prefix("not=") {
+                Some(value) if seg_type == SegType::String => {
+                    push_excluded(&mut constraints, value)?;
+                    excluding = true;
+                }
+                _ => apply_modifier(seg_type, &mut constraints, modifier)?,
+            }
+        }
+    }
+    if seg_type == SegType::Bytes && constraints.byte_len.is_none()
+        || constraints.clamp && constraints.int_min.is_none() && constraints.int_max.is_none()
+    {
+        return Err(ParserConfigError::InvalidModifier);
+    }
+    Ok((seg_type, constraints))
+}
+
+fn push_excluded(constraints: &mut Constraints, value: &str) -> Result<(), ParserConfigError> {
+    if value.is_empty() {
+        return Err(ParserConfigError::InvalidModifier);
+    }
+    constraints.excluded.push(value.to_string());
+    Ok(())
+}
+
+fn apply_modifier(
+    seg_type: SegType,
+    constraints: &mut Constraints,
+    modifier: &str,
+) -> Result<(), ParserConfigError> {
+    let (key, value) = match modifier.split_once('=') {
+        // the `=` of an inclusive range like `1..=99`
+        Some((key, _)) if key.ends_with("..") => (modifier, ""),
+        pair => pair.unwrap_or((modifier, "")),
+    };
+    match (seg_type, key) {
+        (_, "strip_prefix") if !value.is_empty() => {
+            constraints.strip_prefix = Some(value.to_string())
+        }
+        (SegType::String, "max_bytes") => constraints.max_bytes = Some(parse_usize(value)?),
+        (SegType::String, "allow_empty") => {
+            constraints.allow_empty = match value {
+                "" | "true" => Some(true),
+                "false" => Some(false),
+                _ => return Err(ParserConfigError::InvalidModifier),
+            }
+        }
+        // `=32`, exactly 32 chars
+        (SegType::String, "") if !value.is_empty() => {
+            let len = parse_usize(value)?;
+            constraints.min_len = Some(len);
+            constraints.max_len = Some(len);
+        }
+        (SegType::String, "len") => {
+            let (min, max) = parse_len(value)?;
+            constraints.min_len = min;
+            constraints.max_len = max;
+        }
+        (SegType::String, "lowercase") => constraints.lowercase = true,
+        #[cfg(feature = "unicode")]
+        (SegType::String, "graphemes") => constraints.graphemes = true,
+        #[cfg(feature = "unicode")]
+        (SegType::String, "script") if !value.is_empty() => {
+            constraints.scripts = value.split('|').map(str::parse).collect::<Result<_, _>>()?
+        }
+        #[cfg(feature = "transliterate")]
+        (SegType::String, "ascii") => constraints.ascii = true,
+        #[cfg(feature = "idna")]
+        (SegType::String, "idn") => constraints.idn = true,
+        #[cfg(feature = "base58")]
+        (SegType::Base58, "decode") => constraints.decode = true,
+        (SegType::Uuid, version) if version.starts_with('v') => {
+            constraints.uuid_version = match version[1..].parse() {
+                Ok(v @ 1..=8) => Some(v),
+                _ => return Err(ParserConfigError::InvalidModifier),
+            }
+        }
+        (SegType::Number, "unit") if !value.is_empty() => {
+            constraints.units = value.split('|').map(str::to_string).collect()
+        }
+        (SegType::Number, range) if range.contains("..") => {
+            (
+                constraints.num_min,
+                constraints.num_max,
+                constraints.num_max_exclusive,
+            ) = parse_num_range(range)?
+        }
+        (SegType::Number, "capture_unit") => constraints.capture_unit = true,
+        (SegType::Number, "auto") => constraints.auto = true,
+        (SegType::Number, "special") => constraints.special = true,
+        (SegType::Date, "components") => constraints.components = true,
+        (SegType::Jwt, "decode") => constraints.decode = true,
+        (SegType::Path, "no_dots") => constraints.path_checks.no_dots = true,
+        (SegType::Path, "no_empty") => constraints.path_checks.no_empty = true,
+        (SegType::Integer, range) if range.contains("..") => {
+            (constraints.int_min, constraints.int_max) = parse_int_range(range)?
+        }
+        (SegType::Integer, "clamp") => constraints.clamp = true,
+        // `,` already separates modifiers, so it's spelled out
+        (SegType::Integer, "sep") => {
+            let mut chars = value.chars();
+            constraints.group_separator = match (value, chars.next(), chars.next()) {
+                ("comma", _, _) => Some(','),
+                (_, Some(c), None) if !c.is_ascii_digit() && !matches!(c, '-' | '+') => Some(c),
+                _ => return Err(ParserConfigError::InvalidModifier),
+            }
+        }
+        (SegType::Bytes, "hex") => {}
+        (SegType::Bytes, len) if constraints.byte_len.is_none() => {
+            constraints.byte_len = match parse_usize(len)? {
+                0 => return Err(ParserConfigError::InvalidModifier),
+                len => Some(len),
+            }
+        }
+        (SegType::Month, "locale") => constraints.locale = value.parse()?,
+        (SegType::Time, "frac") => constraints.time_format = TimeFormat::Fractional,
+        (SegType::Time, "12h") => constraints.time_format = TimeFormat::TwelveHour,
+        _ => return Err(ParserConfigError::InvalidModifier),
+    }
+    Ok(())
+}
+
+/// Parses `N`, `MIN..=MAX`, `MIN..` or `..=MAX`.
+fn parse_len(value: &str) -> Result<(Option<usize>, Option<usize>), ParserConfigError> {
+    let Some((min, max)) = value.split_once("..") else {
+        let len = parse_usize(value)?;
+        return Ok((Some(len), Some(len)));
+    };
+    let min = (!min.is_empty()).then(|| parse_usize(min)).transpose()?;
+    let max = match max.strip_prefix('=') {
+        Some(max) => Some(parse_usize(max)?),
+        None if max.is_empty() => None,
+        None => return Err(ParserConfigError::InvalidModifier),
+    };
+    check_bounds(min, max)
+}
+
+/// Parses a Rust-style integer range (`1..100`, `1..=99`, `1..`, `..=99`)
+/// into inclusive bounds.
+fn parse_int_range(value: &str) -> Result<(Option<i64>, Option<i64>), ParserConfigError> {
+    let parse = |n: &str| {
+        n.parse::<i64>()
+            .map_err(|_| ParserConfigError::InvalidModifier)
+    };
+    let (min, max) = value
+        .split_once("..")
+        .ok_or(ParserConfigError::InvalidModifier)?;
+    let min = (!min.is_empty()).then(|| parse(min)).transpose()?;
+    let max = match max.strip_prefix('=') {
+        Some(max) => Some(parse(max)?),
+        None if max.is_empty() => None,
+        None => Some(
+            parse(max)?
+                .checked_sub(1)
+                .ok_or(ParserConfigError::InvalidModifier)?,
+        ),
+    };
+    check_bounds(min, max)
+}
+
+/// Parses a number range (`0.5..=1`, `1..100`, `0..`, `..=1e3`). An
+/// exclusive max is flagged rather than rounded, as floats have no
+/// nearest value below it.
+fn parse_num_range(value: &str) -> Result<(Option<f64>, Option<f64>, bool), ParserConfigError> {
+    let parse = |n: &str| match n.parse::<f64>() {
+        Ok(n) if n.is_finite() => Ok(n),
+        _ => Err(ParserConfigError::InvalidModifier),
+    };
+    let (min, max) = value
+        .split_once("..")
+        .ok_or(ParserConfigError::InvalidModifier)?;
+    let min = (!min.is_empty()).then(|| parse(min)).transpose()?;
+    let (max, exclusive) = match max.strip_prefix('=') {
+        Some(max) => (Some(parse(max)?), false),
+        None if max.is_empty() => (None, false),
+        None => (Some(parse(max)?), true),
+    };
+    // `1..1` is empty
+    if exclusive && min == max {
+        return Err(ParserConfigError::InvalidModifier);
+    }
+    let (min, max) = check_bounds(min, max)?;
+    Ok((min, max, exclusive))
+}
+
+/// Rejects a range with neither bound, or with `min` above `max`.
+pub(crate) fn check_bounds<N: PartialOrd>(
+    min: Option<N>,
+    max: Option<N>,
+) -> Result<(Option<N>, Option<N>), ParserConfigError> {
+    match (min, max) {
+        (None, None) => Err(ParserConfigError::InvalidModifier),
+        (Some(min), Some(max)) if min > max => Err(ParserConfigError::InvalidModifier),
+        bounds => Ok(bounds),
+    }
+}
+
+fn parse_usize(value: &str) -> Result<usize, ParserConfigError> {
+    value
+        .parse()
+        .map_err(|_| ParserConfigError::InvalidModifier)
+}
+
+impl TryFrom<&str> for Var {
+    type Error = ParserConfigError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        parse_var(value, &ParseOptions::default())
+    }
+}
+
+fn parse_var(value: &str, options: &ParseOptions) -> Result<Var, ParserConfigError> {
+    let (value, optional) = match value.strip_suffix('?') {
+        Some(value) => (value, true),
+        None => (value, false),
+    };
+    let (name, spec) = match value.split_once('<') {
+        Some((name, rest)) => (
+            name,
+            rest.strip_suffix('>')
+                .ok_or(ParserConfigError::InvalidVar)?,
+        ),
+        None => (value, ""),
+    };
+    if !is_valid_name(name) {
+        return Err(ParserConfigError::InvalidVar);
+    }
+    let (spec, default) = split_default(spec);
+    let (seg_type, constraints) = parse_type_spec(spec, options)?;
+    let mut var = Var::with_constraints(name.to_string(), seg_type, constraints);
+    if optional {
+        var = var.optional();
+    }
+    if let Some(default) = default {
+        if optional {
+            return Err(ParserConfigError::InvalidDefault);
+        }
+        var = var.with_default(default);
+        if var.try_match(default).is_err() {
+            return Err(ParserConfigError::InvalidDefault);
+        }
+    }
+    Ok(var)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_pattern_ok() {
+        let pattern: Pattern = "/users/:id<number>/".parse().unwrap();
+        assert_eq!(
+            pattern.segments(),
+            &[
+                Segment::Static("users".to_string()),
+                Segment::Var(Var::new("id".to_string(), SegType::Number)),
+                Segment::Terminus,
+            ]
+        );
+    }
+    #[test]
+    fn parse_pattern_explicit_terminus() {
+        let pattern: Pattern = "/users/:id<number>/$".parse().unwrap();
+        assert_eq!(pattern, "/users/:id<number>/".parse().unwrap());
+        assert!(pattern.match_path("/users/5").is_ok());
+        assert!(pattern.match_path("/users/5/").is_ok());
+        assert_eq!(
+            pattern.match_path("/users/5/extra"),
+            Err(crate::errors::MatchError::NotTerminated {
+                got: "extra".to_string()
+            })
+        );
+        for bad in ["/users/$/:id", "/$/", "/users/$/$"] {
+            assert!(
+                matches!(
+                    bad.parse::<Pattern>(),
+                    Err(ParserConfigError::MisplacedTerminus)
+                ),
+                "{bad}"
+            );
+        }
+        // `$` inside a static is still literal
+        assert!("/price$"
+            .parse::<Pattern>()
+            .unwrap()
+            .match_path("/price$")
+            .is_ok());
+    }
+    #[test]
+    fn parse_pattern_untyped_var_is_string() {
+        let pattern: Pattern = "/:name".parse().unwrap();
+        assert_eq!(
+            pattern.segments(),
+            &[Segment::Var(Var::new("name".to_string(), SegType::String))]
+        );
+    }
+    #[test]
+    fn parse_host_pattern() {
+        let host = Pattern::parse_host(":region<enum.i(eu,us)>.api.example.com").unwrap();
+        assert_eq!(host.segments().len(), 4);
+        assert!(host.match_host("EU.api.example.com.").is_ok());
+        assert!(host.match_host("ap.api.example.com").is_err());
+        assert!(matches!(
+            Pattern::parse_host("example.com/users"),
+            Err(ParserConfigError::InvalidHost)
+        ));
+    }
+    #[test]
+    fn parse_host_rejects_path_only_segments() {
+        assert!(Pattern::parse_host("api.*.example.com").is_ok());
+        for bad in [
+            "example.com.$",
+            "**.example.com",
+            "(www)?.example.com",
+            "example.com.(:sub)*",
+            "example.com?q=:q",
+        ] {
+            assert!(
+                matches!(
+                    Pattern::parse_host(bad),
+                    Err(ParserConfigError::InvalidHost)
+                ),
+                "{bad}"
+            );
+        }
+    }
+    #[test]
+    fn parse_pattern_untyped_var_options() {
+        let options = ParseOptions::default();
+        assert_eq!(
+            options.parse("/:x").unwrap().segments(),
+            &[Segment::Var(Var::new("x".to_string(), SegType::String))]
+        );
+        let options = ParseOptions {
+            untyped: Some(SegType::Integer),
+        };
+        assert_eq!(
+            options.parse("/:x").unwrap().segments(),
+            &[Segment::Var(Var::new("x".to_string(), SegType::Integer))]
+        );
+        assert_eq!(
+            options.parse("/:x<string>").unwrap().segments(),
+            &[Segment::Var(Var::new("x".to_string(), SegType::String))]
+        );
+        assert!(options.parse("/:x<=abc>").is_err());
+        let options = ParseOptions { untyped: None };
+        assert!(matches!(
+            options.parse("/a/:x"),
+            Err(ParserConfigError::UntypedVar)
+        ));
+        assert!(options.parse("/a/:x<number>").is_ok());
+    }
+    #[test]
+    fn parse_pattern_repeat_group() {
+        let pattern: Pattern = "/filter/(:k<string>/:v<number>)*".parse().unwrap();
+        assert_eq!(
+            pattern.segments()[1],
+            Segment::Repeat(vec![
+                Segment::Var(Var::new("k".to_string(), SegType::String)),
+                Segment::Var(Var::new("v".to_string(), SegType::Number)),
+            ])
+        );
+    }
+    #[test]
+    fn parse_pattern_repeat_group_not_last_err() {
+        assert!(matches!(
+            "/(:k/:v)*/tail".parse::<Pattern>(),
+            Err(ParserConfigError::InvalidGroup)
+        ));
+        assert!(matches!(
+            "/((:k)*)*".parse::<Pattern>(),
+            Err(ParserConfigError::InvalidGroup)
+        ));
+    }
+    #[test]
+    fn parse_pattern_trailing_comment_and_whitespace() {
+        let pattern: Pattern = "   /users/:id<number>\t  # user detail # really\n"
+            .parse()
+            .unwrap();
+        assert_eq!(pattern, "/users/:id<number>".parse().unwrap());
+    }
+    #[test]
+    fn parse_pattern_comment_placement_err() {
+        assert!(matches!(
+            "/users/:id<number># detail".parse::<Pattern>(),
+            Err(ParserConfigError::AmbiguousComment)
+        ));
+        assert!(matches!(
+            "/users/ :id<number>".parse::<Pattern>(),
+            Err(ParserConfigError::UnexpectedWhitespace)
+        ));
+        assert!(matches!(
+            "  # just a comment".parse::<Pattern>(),
+            Err(ParserConfigError::EmptyPattern)
+        ));
+    }
+    #[test]
+    fn parse_pattern_optional_vars() {
+        let pattern: Pattern = "/posts/:tag?/:page<integer=1>/".parse().unwrap();
+        assert_eq!(
+            pattern.segments()[1],
+            Segment::Var(Var::new("tag".to_string(), SegType::String).optional())
+        );
+        assert_eq!(
+            pattern.segments()[2],
+            Segment::Var(Var::new("page".to_string(), SegType::Integer).with_default("1"))
+        );
+    }
+    #[test]
+    fn parse_pattern_optional_vars_err() {
+        assert!(matches!(
+            "/:tag?/posts".parse::<Pattern>(),
+            Err(ParserConfigError::InvalidOptional)
+        ));
+        assert!(matches!(
+            "/:page<integer=one>".parse::<Pattern>(),
+            Err(ParserConfigError::InvalidDefault)
+        ));
+        assert!(matches!(
+            "/(:k?/:v)*".parse::<Pattern>(),
+            Err(ParserConfigError::InvalidGroup)
+        ));
+    }
+    #[test]
+    fn parse_var_max_bytes() {
+        let var = Var::try_from("name<string(max_bytes=32)>").unwrap();
+        assert_eq!(
+            var,
+            Var::with_constraints(
+                "name".to_string(),
+                SegType::String,
+                Constraints {
+                    max_bytes: Some(32),
+                    ..Default::default()
+                }
+            )
+        );
+    }
+    #[test]
+    fn parse_var_time_format() {
+        let var = Var::try_from("t<time(12h)>").unwrap();
+        assert_eq!(var.to_string(), ":t<time(12h)>");
+        assert!(Var::try_from("t<time(24h)>").is_err());
+    }
+    #[test]
+    fn parse_var_uuid_version() {
+        let var = Var::try_from("id<uuid(v7)>").unwrap();
+        assert_eq!(var.to_string(), ":id<uuid(v7)>");
+        assert!(Var::try_from("id<uuid(v9)>").is_err());
+    }
+    #[test]
+    fn parse_var_template() {
+        let var = Var::try_from("v<template(thumb-{id}.{ext})>").unwrap();
+        assert_eq!(var.to_string(), ":v<template(thumb-{id}.{ext})>");
+        assert!(matches!(
+            Var::try_from("v<template>"),
+            Err(ParserConfigError::InvalidTemplate)
+        ));
+    }
+    #[test]
+    fn parse_var_number_units() {
+        let var = Var::try_from("w<number(unit=px|rem,capture_unit)>").unwrap();
+        assert_eq!(var.to_string(), ":w<number(unit=px|rem,capture_unit)>");
+        assert!(Var::try_from("w<number(unit=)>").is_err());
+    }
+    #[test]
+    fn parse_var_enum() {
+        let var = Var::try_from("sort<enum.i(asc,desc)>").unwrap();
+        assert_eq!(var.to_string(), ":sort<enum.i(asc,desc)>");
+        assert_eq!(
+            var.try_match("Asc").unwrap(),
+            crate::segments::MatchResult::Matched {
+                value: crate::segments::MatchValue::String("asc".to_string()),
+                name: Some("sort".to_string()),
+                segment_index: None,
+                span: None
+            }
+        );
+        assert!(Var::try_from("sort<enum(ASC,DESC)>")
+            .unwrap()
+            .try_match("Asc")
+            .is_err());
+        assert!(Var::try_from("sort<enum>").is_err());
+        assert!(Var::try_from("s<string.i>").is_err());
+    }
+    #[test]
+    fn parse_pattern_month_names() {
+        let pattern: Pattern = "/archive/:m<month>/:y<integer>".parse().unwrap();
+        let result = pattern.match_path("/archive/January/2021").unwrap();
+        assert_eq!(
+            result[1],
+            crate::segments::MatchResult::Matched {
+                value: crate::segments::MatchValue::Integer(1),
+                name: Some("m".to_string()),
+                segment_index: Some(1),
+                span: Some((9, 16))
+            }
+        );
+    }
+    #[cfg(feature = "locales")]
+    #[test]
+    fn parse_pattern_localized_month_names() {
+        let pattern: Pattern = "/archive/:m<month(locale=fr)>".parse().unwrap();
+        assert_eq!(pattern.to_string(), "/archive/:m<month(locale=fr)>");
+        let result = pattern.match_path("/archive/août").unwrap();
+        assert_eq!(
+            result[1],
+            crate::segments::MatchResult::Matched {
+                value: crate::segments::MatchValue::Integer(8),
+                name: Some("m".to_string()),
+                segment_index: Some(1),
+                span: Some((9, 14))
+            }
+        );
+        assert!(pattern.match_path("/archive/August").is_err());
+    }
+    #[test]
+    fn parse_var_strip_prefix() {
+        let var = Var::try_from("id<string(strip_prefix=user_)>").unwrap();
+        assert_eq!(var.to_string(), ":id<string(strip_prefix=user_)>");
+        assert!(Var::try_from("id<string(strip_prefix=)>").is_err());
+    }
+    #[test]
+    fn parse_var_inclusive_ranges() {
+        let var = Var::try_from("n<integer(1..=99)>").unwrap();
+        assert_eq!(var.constraints().int_max, Some(99));
+        assert_eq!(var.to_string(), ":n<integer(1..=99)>");
+        let var = Var::try_from("n<number(0.5..=1)>").unwrap();
+        assert_eq!(var.constraints().num_min, Some(0.5));
+        assert_eq!(var.to_string(), ":n<number(0.5..=1)>");
+        assert!(Var::try_from("n<number(1..1)>").is_err());
+        assert!(Var::try_from("n<number(..=inf)>").is_err());
+    }
+    #[test]
+    fn parse_var_group_separator() {
+        let var = Var::try_from("n<integer(sep=_)>").unwrap();
+        assert_eq!(var.to_string(), ":n<integer(sep=_)>");
+        assert!(Var::try_from("n<integer(sep=4)>").is_err());
+        assert!(Var::try_from("n<integer(sep=__)>").is_err());
+        assert!(Var::try_from("n<number(sep=_)>").is_err());
+    }
+    #[test]
+    fn parse_pattern_optional_group_err() {
+        for bad in [
+            "/(a/:b?)?",
+            "/((a)?)?",
+            "/((a)*)?",
+            "/()?",
+            "/x:e<enum(a,b)>",
+        ] {
+            assert!(bad.parse::<Pattern>().is_err(), "{bad}");
+        }
+        let five = "/(a)?".repeat(MAX_OPTIONAL_GROUPS + 1);
+        assert!(matches!(
+            five.parse::<Pattern>(),
+            Err(ParserConfigError::InvalidGroup)
+        ));
+        assert!("/(a)?"
+            .repeat(MAX_OPTIONAL_GROUPS)
+            .parse::<Pattern>()
+            .is_ok());
+    }
+    #[test]
+    fn parse_slash_group_only_outside_var_specs() {
+        let pattern: Pattern = "/api/(v:version<number>/)?users".parse().unwrap();
+        assert_eq!(
+            pattern.to_string(),
+            "/api/(:version<number(strip_prefix=v)>)?/users"
+        );
+        // a `/)?` inside a var spec is part of the spec, not a group
+        let pattern: Pattern = "/f/:x<enum(a/)?b,c)>".parse().unwrap();
+        assert_eq!(pattern.to_string(), "/f/:x<enum(a/)?b,c)>");
+        assert_eq!(
+            source_specificity("/f/:x<enum(a/)?b,c)>"),
+            pattern.specificity()
+        );
+    }
+    #[test]
+    fn source_specificity_matches_parsed() {
+        for source in [
+            "/",
+            "/users/:id<number>/",
+            "/api/(v:version<number>/)?users",
+            "/f/(:k/:v)*  # filters",
+            "/x/v:n<integer>/:rest?",
+            "/assets/**path/*.css",
+        ] {
+            let pattern: Pattern = source.parse().unwrap();
+            assert_eq!(
+                source_specificity(source),
+                pattern.specificity(),
+                "{source}"
+            );
+        }
+    }
+    #[test]
+    fn parse_pattern_catch_all() {
+        let pattern: Pattern = "/assets/**path/*.css".parse().unwrap();
+        assert_eq!(
+            pattern.segments()[1..],
+            [
+                Segment::CatchAll(Some("path".to_string()), RestChecks::default()),
+                Segment::Glob("*.css".to_string())
+            ]
+        );
+        assert_eq!(pattern.to_string(), "/assets/**path/*.css");
+        for bad in [
+            "/**/",
+            "/**/**",
+            "/**/(a)?",
+            "/**/:x?",
+            "/(**)*",
+            "/**bad-name",
+            "/**path(no_slashes)",
+            "/**path(no_dots",
+        ] {
+            assert!(bad.parse::<Pattern>().is_err(), "{bad}");
+        }
+    }
+    #[test]
+    fn parse_var_bad_modifier_err() {
+        assert!(matches!(
+            Var::try_from("n<number(max_bytes=32)>"),
+            Err(ParserConfigError::InvalidModifier)
+        ));
+    }
+    #[test]
+    fn parse_pattern_bad_static_err() {
+        assert!(matches!(
+            "/users//posts".parse::<Pattern>(),
+            Err(ParserConfigError::InvalidStatic)
+        ));
+    }
+}
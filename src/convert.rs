@@ -0,0 +1,436 @@
+use crate::config::{is_valid_name, is_valid_static};
+use crate::errors::{ConvertError, ParserConfigError};
+use crate::pattern::Pattern;
+use crate::segments::{Constraints, SegType, Segment, TimeFormat, Var};
+use crate::template::TemplatePart;
+
+const NUMBER: &str = r"[+-]?(?:\d+\.?\d*|\.\d+)(?:[eE][+-]?\d+)?";
+const HEX: &str = "[0-9a-fA-F]";
+
+impl Pattern {
+    /// Translates the pattern into a single anchored regex, with a named
+    /// group (`(?P<name>...)`) per var. The regex checks shape only: dates
+    /// are `YYYY-MM-DD`-shaped and integers aren't range-checked, so a
+    /// regex match still needs `match_path` to get typed values. Repeated
+    /// groups, byte-length limits and other constraints with no regex
+    /// equivalent are errors.
+    ///
+    /// The output is in the syntax of Rust's `regex` crate, which PCRE and
+    /// Python's `re` also accept: `(?P<name>...)` groups, inline `(?i:...)`
+    /// flags and backslash escapes on metacharacters only.
+    pub fn to_regex(&self) -> Result<String, ConvertError> {
+        if let Some(param) = self.query().first() {
+            let param = format!("{}={}", param.key, param.value);
+            return Err(unsupported(&param, "query params match in any order"));
+        }
+        let mut regex = String::from("^");
+        for (i, segment) in self.segments().iter().enumerate() {
+            let sep = if i == 0 { "/?" } else { "/" };
+            match segment {
+                Segment::Static(s) => {
+                    regex.push_str(sep);
+                    regex.push_str(&escape(s));
+                }
+                Segment::Terminus => regex.push_str("/?"),
+                Segment::Var(v) => {
+                    let group = var_regex(v)?;
+                    if v.is_optional() {
+                        regex.push_str(&format!("(?:{sep}{group})?"));
+                    } else {
+                        regex.push_str(sep);
+                        regex.push_str(&group);
+                    }
+                }
+                Segment::Repeat(_) => {
+                    return Err(unsupported(segment, "repeated groups can't be captured"))
+                }
+                Segment::CatchAll(..) => {
+                    return Err(unsupported(
+                        segment,
+                        "catch-alls capture past their own segment",
+                    ))
+                }
+                Segment::Glob(glob) => {
+                    regex.push_str(sep);
+                    regex.push_str(&glob_regex(glob));
+                }
+                Segment::Optional(group) => {
+                    let inner = group
+                        .iter()
+                        .map(|segment| match segment {
+                            Segment::Static(s) => Ok(escape(s)),
+                            Segment::Glob(glob) => Ok(glob_regex(glob)),
+                            Segment::Var(v) => var_regex(v),
+                            _ => Err(unsupported(segment, "not allowed in an optional group")),
+                        })
+                        .collect::<Result<Vec<_>, _>>()?;
+                    regex.push_str(&format!("(?:{sep}{})?", inner.join("/")));
+                }
+            }
+        }
+        regex.push('$');
+        Ok(regex)
+    }
+    /// Translates the pattern into an RFC 6570 level-1 URI Template, like
+    /// `/users/{id}`. Var types are dropped, so only unconstrained,
+    /// required vars are allowed; anything that consumes a varying number
+    /// of segments, and query params, are errors.
+    pub fn to_uri_template(&self) -> Result<String, ConvertError> {
+        let unsupported = |segment: &dyn std::fmt::Display, reason| ConvertError::Unsupported {
+            segment: segment.to_string(),
+            target: "URI template",
+            reason,
+        };
+        if let Some(param) = self.query().first() {
+            let param = format!("{}={}", param.key, param.value);
+            return Err(unsupported(
+                &param,
+                "level 1 templates have no query expressions",
+            ));
+        }
+        let mut template = String::new();
+        for segment in self.segments() {
+            match segment {
+                Segment::Static(s) => {
+                    template.push('/');
+                    template.push_str(s);
+                }
+                Segment::Terminus => template.push('/'),
+                Segment::Var(v) if v.is_optional() => {
+                    return Err(unsupported(segment, "level 1 expressions are required"))
+                }
+                Segment::Var(v) if *v.constraints() != Constraints::default() => {
+                    return Err(unsupported(segment, "constraints would be lost"))
+                }
+                Segment::Var(v) => template.push_str(&format!("/{{{}}}", v.name)),
+                _ => {
+                    return Err(unsupported(
+                        segment,
+                        "level 1 expressions match exactly one segment",
+                    ))
+                }
+            }
+        }
+        if template.is_empty() {
+            template.push('/');
+        }
+        Ok(template)
+    }
+    /// Reads an RFC 6570 level-1 URI Template whose expressions each fill
+    /// a whole segment, making each one a `String` var.
+    pub fn from_uri_template(template: &str) -> Result<Pattern, ParserConfigError> {
+        let body = template.strip_prefix('/').unwrap_or(template);
+        let parts: Vec<&str> = body.split('/').collect();
+        let last = parts.len() - 1;
+        let segments = parts
+            .iter()
+            .enumerate()
+            .map(|(i, part)| {
+                if let Some(name) = part.strip_prefix('{').and_then(|p| p.strip_suffix('}')) {
+                    if !is_valid_name(name) {
+                        return Err(ParserConfigError::InvalidVar);
+                    }
+                    Ok(Segment::Var(Var::new(name.to_string(), SegType::String)))
+                } else if part.is_empty() && i == last {
+                    Ok(Segment::Terminus)
+                } else if is_valid_static(part) && !part.contains(['{', '}']) {
+                    Ok(Segment::Static(part.to_string()))
+                } else {
+                    Err(ParserConfigError::InvalidStatic)
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Pattern::new(segments))
+    }
+}
+
+fn unsupported(segment: &dyn std::fmt::Display, reason: &'static str) -> ConvertError {
+    ConvertError::Unsupported {
+        segment: segment.to_string(),
+        target: "regex",
+        reason,
+    }
+}
+
+fn escape(literal: &str) -> String {
+    let mut escaped = String::with_capacity(literal.len());
+    for c in literal.chars() {
+        if r"\.+*?()|[]{}^$".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn glob_regex(glob: &str) -> String {
+    let pieces: Vec<String> = glob.split('*').map(escape).collect();
+    pieces.join("[^/]*")
+}
+
+fn is_group_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+fn alternation(values: &[&str], case_insensitive: bool) -> String {
+    let escaped: Vec<String> = values.iter().map(|v| escape(v)).collect();
+    let flags = if case_insensitive { "?i:" } else { "?:" };
+    format!("({flags}{})", escaped.join("|"))
+}
+
+fn var_regex(var: &Var) -> Result<String, ConvertError> {
+    if !is_group_name(&var.name) {
+        return Err(unsupported(var, "var name isn't a valid regex group name"));
+    }
+    let constraints = var.constraints();
+    check_constraints(var, constraints)?;
+    let body = if constraints.alternatives.is_empty() {
+        type_regex(var, var.seg_type(), constraints)?
+    } else {
+        let branches = std::iter::once(var.seg_type())
+            .chain(constraints.alternatives.iter().copied())
+            .map(|seg_type| type_regex(var, seg_type, constraints))
+            .collect::<Result<Vec<_>, _>>()?;
+        format!("(?:{})", branches.join("|"))
+    };
+    let units = if constraints.units.is_empty() {
+        String::new()
+    } else {
+        let units: Vec<&str> = constraints.units.iter().map(String::as_str).collect();
+        alternation(&units, false)
+    };
+    let prefix = constraints
+        .strip_prefix
+        .as_deref()
+        .map(escape)
+        .unwrap_or_default();
+    Ok(format!("{prefix}(?P<{}>{body}){units}", var.name))
+}
+
+fn type_regex(
+    var: &Var,
+    seg_type: SegType,
+    constraints: &Constraints,
+) -> Result<String, ConvertError> {
+    Ok(match seg_type {
+        SegType::String => {
+            let min_empty = usize::from(constraints.allow_empty != Some(true));
+            match (constraints.min_len, constraints.max_len) {
+                (None, None) if min_empty == 0 => "[^/]*".to_string(),
+                (None, None) => "[^/]+".to_string(),
+                (min, max) => format!(
+                    "[^/]{{{},{}}}",
+                    min.unwrap_or(0).max(min_empty),
+                    max.map(|m| m.to_string()).unwrap_or_default()
+                ),
+            }
+        }
+        SegType::Number if constraints.special => {
+            format!(r"(?:{NUMBER}|[+-]?(?i:inf|infinity|nan))")
+        }
+        SegType::Number => NUMBER.to_string(),
+        SegType::Integer => match constraints.group_separator {
+            Some(separator) => {
+                let separator = escape(&separator.to_string());
+                format!(r"[+-]?(?:\d{{1,3}}(?:{separator}\d{{3}})+|\d+)")
+            }
+            None => r"[+-]?\d+".to_string(),
+        },
+        SegType::Date => r"\d{4}-\d{2}-\d{2}".to_string(),
+        SegType::Time => match constraints.time_format {
+            TimeFormat::Seconds => r"\d{2}:\d{2}:\d{2}".to_string(),
+            TimeFormat::Fractional => r"\d{2}:\d{2}:\d{2}(?:\.\d{1,9})?".to_string(),
+            TimeFormat::TwelveHour => r"\d{1,2}:\d{2}(?::\d{2})?(?i:am|pm)".to_string(),
+        },
+        SegType::Timestamp => {
+            r"\d{4}-\d{2}-\d{2}[Tt ]\d{2}:\d{2}:\d{2}(?:\.\d{1,9})?(?:[Zz]|[+-]\d{2}:\d{2})"
+                .to_string()
+        }
+        SegType::Uuid => {
+            let version = match constraints.uuid_version {
+                Some(v) => format!("{v}{HEX}{{3}}"),
+                None => format!("{HEX}{{4}}"),
+            };
+            format!("{HEX}{{8}}-{HEX}{{4}}-{version}-{HEX}{{4}}-{HEX}{{12}}")
+        }
+        SegType::Template => {
+            let Some(template) = &constraints.template else {
+                return Err(unsupported(var, "template var has no template"));
+            };
+            let mut body = String::new();
+            for part in template.parts() {
+                match part {
+                    TemplatePart::Literal(literal) => body.push_str(&escape(literal)),
+                    TemplatePart::Capture(name) if is_group_name(name) => {
+                        body.push_str(&format!("(?P<{name}>[^/]+?)"))
+                    }
+                    TemplatePart::Capture(_) => {
+                        return Err(unsupported(
+                            var,
+                            "template capture isn't a valid group name",
+                        ))
+                    }
+                }
+            }
+            body
+        }
+        SegType::Enum => {
+            let values: Vec<&str> = constraints.values.iter().map(String::as_str).collect();
+            alternation(&values, constraints.case_insensitive)
+        }
+        SegType::Month => alternation(&constraints.locale.month_names(), true),
+        SegType::Bytes => match constraints.byte_len {
+            Some(len) => format!("{HEX}{{{}}}", len * 2),
+            None => return Err(unsupported(var, "bytes var has no length")),
+        },
+        SegType::Base58 => "[1-9A-HJ-NP-Za-km-z]+".to_string(),
+        SegType::Jwt => r"[A-Za-z0-9_-]+\.[A-Za-z0-9_-]+\.[A-Za-z0-9_-]*".to_string(),
+        SegType::Path => {
+            return Err(unsupported(
+                var,
+                "percent-decoded path checks have no regex equivalent",
+            ))
+        }
+    })
+}
+
+fn check_constraints(var: &Var, constraints: &Constraints) -> Result<(), ConvertError> {
+    if constraints.max_bytes.is_some() {
+        return Err(unsupported(
+            var,
+            "byte-length limits have no regex equivalent",
+        ));
+    }
+    #[cfg(feature = "idna")]
+    if constraints.idn {
+        return Err(unsupported(var, "IDN decoding has no regex equivalent"));
+    }
+    #[cfg(feature = "unicode")]
+    if constraints.graphemes {
+        return Err(unsupported(var, "grapheme counts have no regex equivalent"));
+    }
+    #[cfg(feature = "unicode")]
+    if !constraints.scripts.is_empty() {
+        return Err(unsupported(var, "script checks have no regex equivalent"));
+    }
+    if constraints.lowercase {
+        return Err(unsupported(
+            var,
+            "lowercase checks have no regex equivalent",
+        ));
+    }
+    if constraints.collates() {
+        return Err(unsupported(var, "collation has no regex equivalent"));
+    }
+    if !constraints.excluded.is_empty() {
+        return Err(unsupported(var, "exclusions need lookahead"));
+    }
+    if constraints.capture_unit {
+        return Err(unsupported(var, "unit captures aren't valid group names"));
+    }
+    if constraints.decode && var.seg_type() == SegType::Jwt {
+        return Err(unsupported(
+            var,
+            "JWT part captures aren't valid group names",
+        ));
+    }
+    if constraints.components {
+        return Err(unsupported(
+            var,
+            "date part captures aren't valid group names",
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn to_regex_mixed_pattern() {
+        let pattern: Pattern = "/u/:id<string(strip_prefix=user_)>".parse().unwrap();
+        assert_eq!(pattern.to_regex().unwrap(), r"^/?u/user_(?P<id>[^/]+)$");
+        let pattern: Pattern = "/s/:s<string(len=2..=8)>".parse().unwrap();
+        assert_eq!(pattern.to_regex().unwrap(), r"^/?s/(?P<s>[^/]{2,8})$");
+        let pattern: Pattern = "/a/:a<integer|date>".parse().unwrap();
+        assert_eq!(
+            pattern.to_regex().unwrap(),
+            r"^/?a/(?P<a>(?:[+-]?\d+|\d{4}-\d{2}-\d{2}))$"
+        );
+        let pattern: Pattern = "/c/:sha<bytes(20,hex)>".parse().unwrap();
+        assert_eq!(
+            pattern.to_regex().unwrap(),
+            r"^/?c/(?P<sha>[0-9a-fA-F]{40})$"
+        );
+        let pattern: Pattern = "/css/*.min.css".parse().unwrap();
+        assert_eq!(pattern.to_regex().unwrap(), r"^/?css/[^/]*\.min\.css$");
+        let pattern: Pattern = "/n/:n<integer(sep=comma)>".parse().unwrap();
+        assert_eq!(
+            pattern.to_regex().unwrap(),
+            r"^/?n/(?P<n>[+-]?(?:\d{1,3}(?:,\d{3})+|\d+))$"
+        );
+        let pattern: Pattern = "/users/:id<integer>/posts/:day<date>/:sort<enum.i(asc,desc)>/"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            pattern.to_regex().unwrap(),
+            r"^/?users/(?P<id>[+-]?\d+)/posts/(?P<day>\d{4}-\d{2}-\d{2})/(?P<sort>(?i:asc|desc))/?$"
+        );
+    }
+    #[test]
+    fn to_regex_escapes_and_optional() {
+        let pattern: Pattern = "/v1.0/:img<template(thumb-{id}.{ext})>/:w<number(unit=px)>?"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            pattern.to_regex().unwrap(),
+            format!(
+                r"^/?v1\.0/(?P<img>thumb-(?P<id>[^/]+?)\.(?P<ext>[^/]+?))(?:/(?P<w>{NUMBER})(?:px))?$"
+            )
+        );
+    }
+    #[test]
+    fn to_regex_unsupported() {
+        let pattern: Pattern = "/filter/(:k/:v)*".parse().unwrap();
+        assert!(matches!(
+            pattern.to_regex(),
+            Err(ConvertError::Unsupported { .. })
+        ));
+        let pattern: Pattern = "/:s<string(max_bytes=4)>".parse().unwrap();
+        assert!(pattern.to_regex().is_err());
+    }
+    #[test]
+    fn uri_template_round_trip() {
+        let pattern: Pattern = "/users/:id/posts/:slug/".parse().unwrap();
+        let template = pattern.to_uri_template().unwrap();
+        assert_eq!(template, "/users/{id}/posts/{slug}/");
+        assert_eq!(Pattern::from_uri_template(&template).unwrap(), pattern);
+        // types are dropped on the way out and come back as strings
+        let typed: Pattern = "/users/:id<number>".parse().unwrap();
+        assert_eq!(typed.to_uri_template().unwrap(), "/users/{id}");
+        assert_eq!(
+            Pattern::from_uri_template("/users/{id}")
+                .unwrap()
+                .to_string(),
+            "/users/:id<string>"
+        );
+    }
+    #[test]
+    fn uri_template_unsupported() {
+        for pattern in [
+            "/files/**",
+            "/s/:s<string(max_bytes=8)>",
+            "/p/:page?",
+            "/search?q=:q",
+        ] {
+            let pattern: Pattern = pattern.parse().unwrap();
+            let err = pattern.to_uri_template().unwrap_err();
+            assert!(err.to_string().contains("URI template"), "{err}");
+        }
+        for template in ["/users/{id", "/{a}{b}", "/users/{my-id}", "/x{id}"] {
+            assert!(Pattern::from_uri_template(template).is_err(), "{template}");
+        }
+    }
+}
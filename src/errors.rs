@@ -1,6 +1,285 @@
+use std::fmt;
+
 use thiserror::Error;
+
+use crate::segments::{MatchValue, SegType};
+
 #[derive(Error, Debug)]
 pub enum ParserConfigError {
     #[error("Invalid segment type")]
     InvalidSegmentType,
+    #[error("Invalid segment modifier")]
+    InvalidModifier,
+    #[error("Invalid static segment")]
+    InvalidStatic,
+    #[error("Invalid variable")]
+    InvalidVar,
+    #[error("Invalid segment group")]
+    InvalidGroup,
+    #[error("Invalid template")]
+    InvalidTemplate,
+    #[error("Optional segments must come last")]
+    InvalidOptional,
+    #[error("Default doesn't match its var's type")]
+    InvalidDefault,
+    #[error("Empty pattern")]
+    EmptyPattern,
+    #[error("Comments must be separated from the pattern by whitespace")]
+    AmbiguousComment,
+    #[error("Unexpected whitespace inside pattern")]
+    UnexpectedWhitespace,
+    #[error("Invalid query parameter")]
+    InvalidQuery,
+    #[error("Variable has no type")]
+    UntypedVar,
+    #[error("Invalid host pattern")]
+    InvalidHost,
+    #[error("`$` must be the last segment")]
+    MisplacedTerminus,
+}
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum MatchError {
+    #[error("Expected {expected:?}, got {got:?}")]
+    StaticMismatch { expected: String, got: String },
+    #[error("Expected {expected:?} value, got {got:?}")]
+    InvalidValue { expected: SegType, got: String },
+    #[error("Segment is {len} bytes, max is {max}")]
+    SegmentTooLong { max: usize, len: usize },
+    #[error("Length {len} is outside {min:?}..={max:?}")]
+    LengthOutOfRange {
+        min: Option<usize>,
+        max: Option<usize>,
+        len: usize,
+    },
+    #[error("{got} is outside {min:?}..={max:?}")]
+    OutOfRange {
+        min: Option<i64>,
+        max: Option<i64>,
+        got: i64,
+    },
+    #[error("{got} is outside {min:?}..={max:?}")]
+    NumberOutOfRange {
+        min: Option<f64>,
+        max: Option<f64>,
+        got: f64,
+    },
+    #[error("Expected UUID version {expected}, got version {got}")]
+    UuidVersionMismatch { expected: u8, got: u8 },
+    #[error("Expected a unit suffix from {expected:?}, got {got:?}")]
+    UnknownUnit { expected: Vec<String>, got: String },
+    #[error("Expected {got:?} to start with {expected:?}")]
+    MissingPrefix { expected: String, got: String },
+    #[error("{got:?} is excluded")]
+    Excluded { got: String },
+    #[error("{got:?} isn't lowercase")]
+    NotLowercase { got: String },
+    #[error("{got:?} has characters from a disallowed script")]
+    DisallowedScript { got: String },
+    #[error("Expected end of path, got {got:?}")]
+    NotTerminated { got: String },
+    #[error("Path has more than {max} segments")]
+    TooManySegments { max: usize },
+    #[error("Path ended before pattern")]
+    MissingSegment,
+    #[error("Unexpected trailing input {got:?}")]
+    TrailingInput { got: String },
+    #[error("Timestamp {got:?} has no offset")]
+    MissingOffset { got: String },
+    #[error("Catch-all part {got:?} isn't allowed")]
+    InvalidRestPart { got: String },
+    #[error("Missing query parameter {key:?}")]
+    MissingParam { key: String },
+    #[error("Unexpected query parameter {key:?}")]
+    UnexpectedParam { key: String },
+}
+
+/// Why a match failed, in broad strokes; see `MatchError::kind`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchErrorKind {
+    /// The path's shape differs from the pattern's: a static segment or
+    /// query param doesn't match, or there are too few or too many parts.
+    Structure,
+    /// A var's input isn't a value of its type.
+    Parse,
+    /// A var's value parsed but breaks one of its constraints.
+    Constraint,
+    /// The pattern ends with a terminus and the path goes on.
+    Terminus,
+}
+
+impl MatchError {
+    /// The failure's category, to branch on without listing every variant.
+    pub fn kind(&self) -> MatchErrorKind {
+        match self {
+            MatchError::StaticMismatch { .. }
+            | MatchError::TooManySegments { .. }
+            | MatchError::MissingSegment
+            | MatchError::TrailingInput { .. }
+            | MatchError::MissingParam { .. }
+            | MatchError::UnexpectedParam { .. } => MatchErrorKind::Structure,
+            MatchError::InvalidValue { .. } | MatchError::MissingOffset { .. } => {
+                MatchErrorKind::Parse
+            }
+            MatchError::SegmentTooLong { .. }
+            | MatchError::LengthOutOfRange { .. }
+            | MatchError::OutOfRange { .. }
+            | MatchError::NumberOutOfRange { .. }
+            | MatchError::UuidVersionMismatch { .. }
+            | MatchError::UnknownUnit { .. }
+            | MatchError::MissingPrefix { .. }
+            | MatchError::Excluded { .. }
+            | MatchError::NotLowercase { .. }
+            | MatchError::DisallowedScript { .. }
+            | MatchError::InvalidRestPart { .. } => MatchErrorKind::Constraint,
+            MatchError::NotTerminated { .. } => MatchErrorKind::Terminus,
+        }
+    }
+    /// Renders the error with any text taken from the path replaced by
+    /// `[redacted]`, for logging without leaking tokens or emails.
+    pub fn redacted(&self) -> Redacted<'_> {
+        Redacted(self)
+    }
+}
+
+/// See `MatchError::redacted`.
+#[derive(Debug, Clone, Copy)]
+pub struct Redacted<'a>(&'a MatchError);
+
+const REDACTED: &str = "[redacted]";
+
+impl fmt::Display for Redacted<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            MatchError::StaticMismatch { expected, .. } => {
+                write!(f, "Expected {expected:?}, got {REDACTED}")
+            }
+            MatchError::InvalidValue { expected, .. } => {
+                write!(f, "Expected {expected:?} value, got {REDACTED}")
+            }
+            MatchError::OutOfRange { min, max, .. } => {
+                write!(f, "{REDACTED} is outside {min:?}..={max:?}")
+            }
+            MatchError::NumberOutOfRange { min, max, .. } => {
+                write!(f, "{REDACTED} is outside {min:?}..={max:?}")
+            }
+            MatchError::UnknownUnit { expected, .. } => {
+                write!(
+                    f,
+                    "Expected a unit suffix from {expected:?}, got {REDACTED}"
+                )
+            }
+            MatchError::MissingPrefix { expected, .. } => {
+                write!(f, "Expected {REDACTED} to start with {expected:?}")
+            }
+            MatchError::Excluded { .. } => write!(f, "{REDACTED} is excluded"),
+            MatchError::NotLowercase { .. } => write!(f, "{REDACTED} isn't lowercase"),
+            MatchError::DisallowedScript { .. } => {
+                write!(f, "{REDACTED} has characters from a disallowed script")
+            }
+            MatchError::NotTerminated { .. } => {
+                write!(f, "Expected end of path, got {REDACTED}")
+            }
+            MatchError::TrailingInput { .. } => {
+                write!(f, "Unexpected trailing input {REDACTED}")
+            }
+            MatchError::MissingOffset { .. } => write!(f, "Timestamp {REDACTED} has no offset"),
+            MatchError::InvalidRestPart { .. } => {
+                write!(f, "Catch-all part {REDACTED} isn't allowed")
+            }
+            MatchError::UnexpectedParam { .. } => {
+                write!(f, "Unexpected query parameter {REDACTED}")
+            }
+            // these carry no input text, only lengths, counts, a UUID
+            // version or what the pattern declared, so they're shown as is
+            error @ (MatchError::SegmentTooLong { .. }
+            | MatchError::LengthOutOfRange { .. }
+            | MatchError::UuidVersionMismatch { .. }
+            | MatchError::TooManySegments { .. }
+            | MatchError::MissingSegment
+            | MatchError::MissingParam { .. }) => write!(f, "{error}"),
+        }
+    }
+}
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ConvertError {
+    #[error("{segment} can't be converted to a {target}: {reason}")]
+    Unsupported {
+        segment: String,
+        target: &'static str,
+        reason: &'static str,
+    },
+}
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum BuildError {
+    #[error("No value for {name:?}")]
+    Missing { name: String },
+    #[error("{value:?} isn't a valid value for {name:?}")]
+    InvalidValue { name: String, value: MatchValue },
+    #[error("{segment} can't be built")]
+    Unsupported { segment: String },
+}
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum MergeError {
+    #[error("Both matches capture {name:?}")]
+    Clash { name: String },
+}
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ValueError {
+    #[error("Terminus marks the end of a path and has no value")]
+    Terminus,
+    #[error("Expected {expected} value, got {got}")]
+    WrongType {
+        expected: &'static str,
+        got: &'static str,
+    },
+}
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum RouterError {
+    #[error("A route structurally equal to {pattern:?} already exists")]
+    DuplicatePattern { pattern: String },
+    #[error("{pattern:?} can't be mounted: it can't be followed by the rest of a path")]
+    InvalidMount { pattern: String },
+    #[error("{pattern:?} can never match: {by:?} matches every path it does first")]
+    ShadowedRoute { pattern: String, by: String },
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn match_error_redacted() {
+        let error = MatchError::InvalidValue {
+            expected: SegType::Integer,
+            got: "secret-token".to_string(),
+        };
+        assert!(error.to_string().contains("secret-token"));
+        let redacted = error.redacted().to_string();
+        assert_eq!(redacted, "Expected Integer value, got [redacted]");
+        let error = MatchError::OutOfRange {
+            min: Some(1),
+            max: None,
+            got: 12345,
+        };
+        assert!(!error.redacted().to_string().contains("12345"));
+        assert_eq!(
+            MatchError::MissingSegment.redacted().to_string(),
+            MatchError::MissingSegment.to_string()
+        );
+    }
+    #[test]
+    fn match_error_kind() {
+        let pattern: crate::pattern::Pattern = "/items/:n<number(1..=10)>/$".parse().unwrap();
+        let kind = |path| pattern.match_path(path).unwrap_err().kind();
+        assert_eq!(kind("/users/5/"), MatchErrorKind::Structure);
+        assert_eq!(kind("/items/five/"), MatchErrorKind::Parse);
+        assert_eq!(kind("/items/50/"), MatchErrorKind::Constraint);
+        assert_eq!(kind("/items/5/more"), MatchErrorKind::Terminus);
+    }
 }
@@ -0,0 +1,136 @@
+use crate::errors::MatchError;
+use crate::pattern::{split_path, Pattern};
+use crate::segments::{MatchResult, Segment};
+
+/// A per-segment account of how a path lines up against a `Pattern`.
+/// Unlike `match_path`, every segment is checked even after a failure,
+/// so tooling can show the whole picture at once.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchExplanation {
+    pub segments: Vec<SegmentExplanation>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SegmentExplanation {
+    pub index: usize,
+    /// The pattern segment as written, or `None` for input past the end of the pattern.
+    pub expected: Option<String>,
+    /// The path segment, or `None` if the path ran out first.
+    pub input: Option<String>,
+    pub outcome: Result<MatchResult, MatchError>,
+}
+impl SegmentExplanation {
+    pub fn is_match(&self) -> bool {
+        self.outcome.is_ok()
+    }
+}
+
+impl MatchExplanation {
+    pub fn is_match(&self) -> bool {
+        self.segments.iter().all(SegmentExplanation::is_match)
+    }
+    /// Index of the first segment that failed to match.
+    pub fn failure_index(&self) -> Option<usize> {
+        self.segments
+            .iter()
+            .find(|s| !s.is_match())
+            .map(|s| s.index)
+    }
+}
+
+impl Pattern {
+    /// Explains how the path, not any query string, lines up with the pattern.
+    pub fn explain(&self, path: &str) -> MatchExplanation {
+        let (path, _) = self.split_query(path);
+        let mut parts = split_path(path).peekable();
+        let mut segments = Vec::with_capacity(self.segments().len());
+        for (index, segment) in self.segments().iter().enumerate() {
+            let part = match segment {
+                Segment::Repeat(_) => {
+                    let consumed: Vec<&str> =
+                        std::iter::from_fn(|| parts.next_if(|p| !p.is_empty())).collect();
+                    Some(consumed.join("/"))
+                }
+                // greedy: the group is taken if the next parts fit it
+                Segment::Optional(group) => {
+                    let ahead: Vec<&str> = parts.clone().take(group.len()).collect();
+                    let ahead = ahead.join("/");
+                    if segment.try_match(&ahead).is_ok() {
+                        parts.nth(group.len() - 1);
+                        Some(ahead)
+                    } else {
+                        None
+                    }
+                }
+                // shown with everything it captures, segments after it included
+                Segment::CatchAll(..) => {
+                    let tail = self.segments().len() - index - 1;
+                    let rest: Vec<&str> = parts.clone().collect();
+                    rest.len().checked_sub(tail).map(|taken| {
+                        parts.by_ref().take(taken).for_each(drop);
+                        rest.join("/")
+                    })
+                }
+                _ => parts.next().map(str::to_string),
+            };
+            let outcome = match part.as_deref() {
+                Some(part) if !(part.is_empty() && segment.is_optional()) => {
+                    segment.try_match(part)
+                }
+                _ => segment
+                    .match_absent()
+                    .unwrap_or(Err(MatchError::MissingSegment)),
+            };
+            segments.push(SegmentExplanation {
+                index,
+                expected: Some(segment.to_string()),
+                input: part,
+                outcome,
+            });
+        }
+        for (offset, rest) in parts.enumerate() {
+            segments.push(SegmentExplanation {
+                index: self.segments().len() + offset,
+                expected: None,
+                input: Some(rest.to_string()),
+                outcome: Err(MatchError::TrailingInput {
+                    got: rest.to_string(),
+                }),
+            });
+        }
+        MatchExplanation { segments }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::segments::SegType;
+
+    #[test]
+    fn explain_marks_failing_segment() {
+        let pattern: Pattern = "/users/:id<number>/:day<date>".parse().unwrap();
+        let explanation = pattern.explain("/users/5/tuesday");
+        assert!(!explanation.is_match());
+        assert_eq!(explanation.failure_index(), Some(2));
+        let failed = &explanation.segments[2];
+        assert_eq!(failed.expected.as_deref(), Some(":day<date>"));
+        assert_eq!(failed.input.as_deref(), Some("tuesday"));
+        assert_eq!(
+            failed.outcome,
+            Err(MatchError::InvalidValue {
+                expected: SegType::Date,
+                got: "tuesday".to_string()
+            })
+        );
+    }
+    #[test]
+    fn explain_continues_past_failure() {
+        let pattern: Pattern = "/users/:id<number>".parse().unwrap();
+        let explanation = pattern.explain("/posts/5/extra");
+        assert_eq!(explanation.failure_index(), Some(0));
+        assert!(explanation.segments[1].is_match());
+        assert_eq!(explanation.segments[2].expected, None);
+        assert!(!explanation.segments[2].is_match());
+    }
+}
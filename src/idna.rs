@@ -0,0 +1,212 @@
+//! Minimal IDNA support for hostname segments: RFC 3492 Punycode plus the
+//! label checks needed to tell a well-formed hostname from garbage. This is
+//! not a full UTS #46 implementation; mapping and normalization beyond ASCII
+//! lowercasing are not performed.
+
+const BASE: u32 = 36;
+const T_MIN: u32 = 1;
+const T_MAX: u32 = 26;
+const SKEW: u32 = 38;
+const DAMP: u32 = 700;
+const INITIAL_BIAS: u32 = 72;
+const INITIAL_N: u32 = 128;
+const ACE_PREFIX: &str = "xn--";
+const MAX_LABEL_LEN: usize = 63;
+
+/// Converts a hostname with any mix of A-labels (`xn--...`) and U-labels to
+/// its all-Unicode form, or `None` if any label is malformed.
+pub fn to_unicode(host: &str) -> Option<String> {
+    host.split('.')
+        .map(label_to_unicode)
+        .collect::<Option<Vec<_>>>()
+        .map(|labels| labels.join("."))
+}
+
+/// Converts a hostname to its all-ASCII form, or `None` if any label is malformed.
+pub fn to_ascii(host: &str) -> Option<String> {
+    to_unicode(host)?
+        .split('.')
+        .map(label_to_ascii)
+        .collect::<Option<Vec<_>>>()
+        .map(|labels| labels.join("."))
+}
+
+fn label_to_unicode(label: &str) -> Option<String> {
+    let label = label.to_lowercase();
+    let unicode = match label.strip_prefix(ACE_PREFIX) {
+        Some(encoded) => {
+            let decoded = decode(encoded)?;
+            // reject non-canonical encodings and A-labels hiding plain ASCII
+            if decoded.is_ascii() || encode(&decoded)? != encoded {
+                return None;
+            }
+            decoded
+        }
+        None => label,
+    };
+    is_valid_label(&unicode).then_some(unicode)
+}
+
+fn label_to_ascii(label: &str) -> Option<String> {
+    if label.is_ascii() {
+        Some(label.to_string())
+    } else {
+        encode(label).map(|encoded| format!("{ACE_PREFIX}{encoded}"))
+    }
+}
+
+fn is_valid_label(label: &str) -> bool {
+    let ascii_len = if label.is_ascii() {
+        label.len()
+    } else {
+        match encode(label) {
+            Some(encoded) => ACE_PREFIX.len() + encoded.len(),
+            None => return false,
+        }
+    };
+    (1..=MAX_LABEL_LEN).contains(&ascii_len)
+        && !label.starts_with('-')
+        && !label.ends_with('-')
+        && label.chars().all(|c| c.is_alphanumeric() || c == '-')
+}
+
+fn threshold(k: u32, bias: u32) -> u32 {
+    if k <= bias {
+        T_MIN
+    } else if k >= bias + T_MAX {
+        T_MAX
+    } else {
+        k - bias
+    }
+}
+
+fn adapt(delta: u32, num_points: u32, first_time: bool) -> u32 {
+    let mut delta = if first_time { delta / DAMP } else { delta / 2 };
+    delta += delta / num_points;
+    let mut k = 0;
+    while delta > ((BASE - T_MIN) * T_MAX) / 2 {
+        delta /= BASE - T_MIN;
+        k += BASE;
+    }
+    k + (BASE - T_MIN + 1) * delta / (delta + SKEW)
+}
+
+fn decode_digit(byte: u8) -> Option<u32> {
+    match byte {
+        b'a'..=b'z' => Some(u32::from(byte - b'a')),
+        b'A'..=b'Z' => Some(u32::from(byte - b'A')),
+        b'0'..=b'9' => Some(u32::from(byte - b'0') + 26),
+        _ => None,
+    }
+}
+
+fn encode_digit(digit: u32) -> char {
+    match digit {
+        0..=25 => char::from(b'a' + digit as u8),
+        _ => char::from(b'0' + (digit - 26) as u8),
+    }
+}
+
+fn decode(input: &str) -> Option<String> {
+    let (basic, extended) = match input.rfind('-') {
+        Some(i) => (&input[..i], &input[i + 1..]),
+        None => ("", input),
+    };
+    if !basic.is_ascii() {
+        return None;
+    }
+    let mut output: Vec<char> = basic.chars().collect();
+    let (mut n, mut i, mut bias) = (INITIAL_N, 0u32, INITIAL_BIAS);
+    let mut bytes = extended.bytes().peekable();
+    while bytes.peek().is_some() {
+        let old_i = i;
+        let mut weight = 1u32;
+        let mut k = BASE;
+        loop {
+            let digit = decode_digit(bytes.next()?)?;
+            i = i.checked_add(digit.checked_mul(weight)?)?;
+            let t = threshold(k, bias);
+            if digit < t {
+                break;
+            }
+            weight = weight.checked_mul(BASE - t)?;
+            k += BASE;
+        }
+        let len = output.len() as u32 + 1;
+        bias = adapt(i - old_i, len, old_i == 0);
+        n = n.checked_add(i / len)?;
+        i %= len;
+        output.insert(i as usize, char::from_u32(n)?);
+        i += 1;
+    }
+    Some(output.into_iter().collect())
+}
+
+fn encode(input: &str) -> Option<String> {
+    let chars: Vec<u32> = input.chars().map(u32::from).collect();
+    let mut output: String = input.chars().filter(char::is_ascii).collect();
+    let basic_len = output.len() as u32;
+    let mut handled = basic_len;
+    if basic_len > 0 {
+        output.push('-');
+    }
+    let (mut n, mut delta, mut bias) = (INITIAL_N, 0u32, INITIAL_BIAS);
+    while (handled as usize) < chars.len() {
+        let m = chars.iter().copied().filter(|&c| c >= n).min()?;
+        delta = delta.checked_add((m - n).checked_mul(handled + 1)?)?;
+        n = m;
+        for &c in &chars {
+            if c < n {
+                delta = delta.checked_add(1)?;
+            }
+            if c == n {
+                let mut q = delta;
+                let mut k = BASE;
+                loop {
+                    let t = threshold(k, bias);
+                    if q < t {
+                        break;
+                    }
+                    output.push(encode_digit(t + (q - t) % (BASE - t)));
+                    q = (q - t) / (BASE - t);
+                    k += BASE;
+                }
+                output.push(encode_digit(q));
+                bias = adapt(delta, handled + 1, handled == basic_len);
+                delta = 0;
+                handled += 1;
+            }
+        }
+        delta += 1;
+        n += 1;
+    }
+    Some(output)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn punycode_roundtrip() {
+        assert_eq!(encode("bücher").as_deref(), Some("bcher-kva"));
+        assert_eq!(decode("bcher-kva").as_deref(), Some("bücher"));
+    }
+    #[test]
+    fn to_unicode_from_a_label() {
+        assert_eq!(
+            to_unicode("xn--mnchen-3ya.de").as_deref(),
+            Some("münchen.de")
+        );
+    }
+    #[test]
+    fn to_ascii_from_u_label() {
+        assert_eq!(to_ascii("münchen.de").as_deref(), Some("xn--mnchen-3ya.de"));
+    }
+    #[test]
+    fn to_unicode_rejects_malformed() {
+        assert!(to_unicode("xn--a!b.de").is_none());
+        assert!(to_unicode("-bad.de").is_none());
+        assert!(to_unicode("foo..de").is_none());
+    }
+}
@@ -0,0 +1,106 @@
+use std::fmt::Write;
+
+use crate::segments::{format_uuid, MatchValue};
+
+impl MatchValue {
+    /// Renders the value as JSON text, for quick interop without going
+    /// through a serializer. Strings, dates and times become JSON strings
+    /// (dates, times and timestamps in ISO 8601), numbers stay numbers
+    /// (non-finite ones become `null`), UUIDs become hyphenated lowercase
+    /// strings, bytes become lowercase hex strings, lists become arrays
+    /// and `Terminus` becomes `null`.
+    ///
+    /// This returns serialized text, not a `serde_json::Value` behind a
+    /// `serde_json` feature, since that dependency isn't available to the
+    /// crate; parse the text to get a value that can be indexed.
+    pub fn to_json(&self) -> String {
+        let mut json = String::new();
+        self.write_json(&mut json);
+        json
+    }
+    fn write_json(&self, out: &mut String) {
+        match self {
+            MatchValue::String(s) => write_string(out, s),
+            MatchValue::Number(n) if n.is_finite() => {
+                let _ = write!(out, "{n}");
+            }
+            MatchValue::Number(_) | MatchValue::Terminus => out.push_str("null"),
+            MatchValue::Integer(i) => {
+                let _ = write!(out, "{i}");
+            }
+            MatchValue::Date(d) => write_string(out, &d.to_string()),
+            MatchValue::Time(t) => write_string(out, &t.to_string()),
+            MatchValue::Timestamp(t) => write_string(out, &t.to_string()),
+            MatchValue::Uuid(u) => write_string(out, &format_uuid(*u)),
+            MatchValue::Bytes(bytes) => {
+                let hex: String = bytes.iter().map(|b| format!("{b:02x}")).collect();
+                write_string(out, &hex);
+            }
+            MatchValue::List(values) => {
+                out.push('[');
+                for (i, value) in values.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    value.write_json(out);
+                }
+                out.push(']');
+            }
+        }
+    }
+}
+
+fn write_string(out: &mut String, s: &str) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if c < ' ' => {
+                let _ = write!(out, "\\u{:04x}", c as u32);
+            }
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn to_json_scalars() {
+        assert_eq!(MatchValue::Number(1.5).to_json(), "1.5");
+        assert_eq!(MatchValue::Number(f64::NAN).to_json(), "null");
+        assert_eq!(MatchValue::Integer(-3).to_json(), "-3");
+        assert_eq!(
+            MatchValue::String("say \"hi\"\n".to_string()).to_json(),
+            r#""say \"hi\"\n""#
+        );
+        assert_eq!(
+            MatchValue::Date(jiff::civil::date(2021, 1, 2)).to_json(),
+            r#""2021-01-02""#
+        );
+        assert_eq!(
+            MatchValue::Uuid(0x67e55044_10b1_426f_9247_bb680e5fe0c8).to_json(),
+            r#""67e55044-10b1-426f-9247-bb680e5fe0c8""#
+        );
+        assert_eq!(MatchValue::Bytes(vec![0xde, 0xad]).to_json(), r#""dead""#);
+        assert_eq!(MatchValue::Terminus.to_json(), "null");
+    }
+    #[test]
+    fn to_json_list() {
+        let list = MatchValue::List(vec![
+            MatchValue::List(vec![
+                MatchValue::String("color".to_string()),
+                MatchValue::Integer(2),
+            ]),
+            MatchValue::List(Vec::new()),
+        ]);
+        assert_eq!(list.to_json(), r#"[["color",2],[]]"#);
+    }
+}
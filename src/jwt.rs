@@ -0,0 +1,63 @@
+//! Structural checks for JSON Web Tokens (see `SegType::Jwt`): three
+//! base64url parts separated by `.`, the first two decoding to JSON
+//! objects. Signatures are never verified, so a token that passes may
+//! still be forged or expired; check it with a JWT library before
+//! trusting its claims.
+
+/// The decoded header and payload JSON of a structurally valid token.
+pub(crate) fn decode(token: &str) -> Option<(String, String)> {
+    let mut parts = token.split('.');
+    let (header, payload, signature) = (parts.next()?, parts.next()?, parts.next()?);
+    if parts.next().is_some() {
+        return None;
+    }
+    // an unsecured token (`"alg": "none"`) has an empty signature
+    decode_base64url(signature)?;
+    Some((json_object(header)?, json_object(payload)?))
+}
+
+fn json_object(part: &str) -> Option<String> {
+    let json = String::from_utf8(decode_base64url(part)?).ok()?;
+    let trimmed = json.trim();
+    (trimmed.starts_with('{') && trimmed.ends_with('}')).then_some(json)
+}
+
+/// Decodes unpadded base64url, as JWTs use.
+fn decode_base64url(input: &str) -> Option<Vec<u8>> {
+    if input.len() % 4 == 1 {
+        return None;
+    }
+    let mut bytes = Vec::with_capacity(input.len() * 3 / 4);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for c in input.bytes() {
+        let digit = match c {
+            b'A'..=b'Z' => c - b'A',
+            b'a'..=b'z' => c - b'a' + 26,
+            b'0'..=b'9' => c - b'0' + 52,
+            b'-' => 62,
+            b'_' => 63,
+            _ => return None,
+        };
+        buffer = (buffer << 6) | u32::from(digit);
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+        }
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn decode_base64url_unpadded() {
+        assert_eq!(decode_base64url("aGk"), Some(b"hi".to_vec()));
+        assert_eq!(decode_base64url("-_8"), Some(vec![0xfb, 0xff]));
+        assert_eq!(decode_base64url("aGk="), None);
+        assert_eq!(decode_base64url("a"), None);
+    }
+}
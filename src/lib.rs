@@ -1,3 +1,28 @@
-// pub mod config;
+#[cfg(feature = "base58")]
+pub mod base58;
+mod build;
+pub mod builder;
+#[cfg(feature = "collation")]
+pub mod collation;
+pub mod config;
+pub mod convert;
 pub mod errors;
+pub mod explain;
+#[cfg(feature = "idna")]
+pub mod idna;
+pub mod json;
+mod jwt;
+pub mod locale;
+pub mod matches;
+pub mod pattern;
+pub mod router;
+pub mod seg_enum;
 pub mod segments;
+pub mod shared;
+pub mod template;
+#[cfg(feature = "transliterate")]
+pub mod transliterate;
+#[cfg(feature = "unicode")]
+pub mod unicode;
+#[cfg(feature = "aho-corasick")]
+pub mod value_set;
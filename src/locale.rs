@@ -0,0 +1,160 @@
+use std::str::FromStr;
+
+use crate::errors::ParserConfigError;
+
+/// Locales for `SegType::Month` name parsing. English is always available;
+/// German (`de`), French (`fr`), Spanish (`es`) and Italian (`it`) need the
+/// `locales` feature. Names are the full month names, compared
+/// case-insensitively; abbreviations aren't accepted.
+#[derive(Debug, Eq, PartialEq, Hash, Copy, Clone, Default)]
+pub enum Locale {
+    #[default]
+    En,
+    #[cfg(feature = "locales")]
+    De,
+    #[cfg(feature = "locales")]
+    Fr,
+    #[cfg(feature = "locales")]
+    Es,
+    #[cfg(feature = "locales")]
+    It,
+}
+
+impl Locale {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Locale::En => "en",
+            #[cfg(feature = "locales")]
+            Locale::De => "de",
+            #[cfg(feature = "locales")]
+            Locale::Fr => "fr",
+            #[cfg(feature = "locales")]
+            Locale::Es => "es",
+            #[cfg(feature = "locales")]
+            Locale::It => "it",
+        }
+    }
+    pub fn month_names(&self) -> [&'static str; 12] {
+        match self {
+            Locale::En => [
+                "january",
+                "february",
+                "march",
+                "april",
+                "may",
+                "june",
+                "july",
+                "august",
+                "september",
+                "october",
+                "november",
+                "december",
+            ],
+            #[cfg(feature = "locales")]
+            Locale::De => [
+                "januar",
+                "februar",
+                "märz",
+                "april",
+                "mai",
+                "juni",
+                "juli",
+                "august",
+                "september",
+                "oktober",
+                "november",
+                "dezember",
+            ],
+            #[cfg(feature = "locales")]
+            Locale::Fr => [
+                "janvier",
+                "février",
+                "mars",
+                "avril",
+                "mai",
+                "juin",
+                "juillet",
+                "août",
+                "septembre",
+                "octobre",
+                "novembre",
+                "décembre",
+            ],
+            #[cfg(feature = "locales")]
+            Locale::Es => [
+                "enero",
+                "febrero",
+                "marzo",
+                "abril",
+                "mayo",
+                "junio",
+                "julio",
+                "agosto",
+                "septiembre",
+                "octubre",
+                "noviembre",
+                "diciembre",
+            ],
+            #[cfg(feature = "locales")]
+            Locale::It => [
+                "gennaio",
+                "febbraio",
+                "marzo",
+                "aprile",
+                "maggio",
+                "giugno",
+                "luglio",
+                "agosto",
+                "settembre",
+                "ottobre",
+                "novembre",
+                "dicembre",
+            ],
+        }
+    }
+    /// The 1-based month number for a month name in this locale.
+    pub fn parse_month(&self, input: &str) -> Option<i8> {
+        self.month_names()
+            .iter()
+            .position(|name| name.chars().eq(input.chars().flat_map(char::to_lowercase)))
+            .map(|i| i as i8 + 1)
+    }
+}
+
+impl FromStr for Locale {
+    type Err = ParserConfigError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "en" => Ok(Locale::En),
+            #[cfg(feature = "locales")]
+            "de" => Ok(Locale::De),
+            #[cfg(feature = "locales")]
+            "fr" => Ok(Locale::Fr),
+            #[cfg(feature = "locales")]
+            "es" => Ok(Locale::Es),
+            #[cfg(feature = "locales")]
+            "it" => Ok(Locale::It),
+            _ => Err(ParserConfigError::InvalidModifier),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_month_english() {
+        assert_eq!(Locale::En.parse_month("January"), Some(1));
+        assert_eq!(Locale::En.parse_month("DECEMBER"), Some(12));
+        assert_eq!(Locale::En.parse_month("Jan"), None);
+    }
+    #[cfg(feature = "locales")]
+    #[test]
+    fn parse_month_localized() {
+        assert_eq!(Locale::De.parse_month("März"), Some(3));
+        assert_eq!(Locale::Fr.parse_month("Février"), Some(2));
+        assert_eq!(Locale::Es.parse_month("enero"), Some(1));
+        assert_eq!(Locale::De.parse_month("March"), None);
+    }
+}
@@ -0,0 +1,206 @@
+use crate::errors::MergeError;
+use crate::segments::{MatchResult, MatchValue};
+
+/// How a named var ended up in a match.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Capture {
+    /// The path supplied a value.
+    Present(MatchValue),
+    /// An optional var the path left out, with no default.
+    Absent,
+    /// An optional var the path left out, filled from its default.
+    Default(MatchValue),
+}
+impl Capture {
+    /// The captured or default value; `None` when absent.
+    pub fn value(&self) -> Option<&MatchValue> {
+        match self {
+            Capture::Present(value) | Capture::Default(value) => Some(value),
+            Capture::Absent => None,
+        }
+    }
+    pub fn is_present(&self) -> bool {
+        matches!(self, Capture::Present(_))
+    }
+}
+
+/// What `Matches::merge` does when both sides capture the same name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MergePolicy {
+    /// Fail with `MergeError::Clash`.
+    #[default]
+    Error,
+    /// Keep the outer capture.
+    OuterWins,
+    /// Replace the outer capture with the inner one.
+    InnerWins,
+}
+
+/// The named captures from a match, in pattern order. Unnamed results
+/// (statics, termini, repeated groups) are left out, as is any
+/// `MatchValue::Terminus`, which is structural rather than a value.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Matches {
+    captures: Vec<(String, Capture)>,
+}
+
+impl Matches {
+    pub fn get(&self, name: &str) -> Option<&Capture> {
+        self.captures
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, capture)| capture)
+    }
+    /// Shorthand for `get(name).and_then(Capture::value)`.
+    pub fn value(&self, name: &str) -> Option<&MatchValue> {
+        self.get(name).and_then(Capture::value)
+    }
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Capture)> {
+        self.captures
+            .iter()
+            .map(|(name, capture)| (name.as_str(), capture))
+    }
+    pub fn len(&self) -> usize {
+        self.captures.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.captures.is_empty()
+    }
+    /// Combines the captures of an outer match (`self`, such as a prefix
+    /// router's) with those of an inner one matched against the rest of the
+    /// path. Outer captures come first and a clashing name keeps the outer
+    /// position, whichever side's value `policy` picks.
+    pub fn merge(mut self, inner: Matches, policy: MergePolicy) -> Result<Matches, MergeError> {
+        for (name, capture) in inner.captures {
+            match self.captures.iter_mut().find(|(n, _)| *n == name) {
+                None => self.captures.push((name, capture)),
+                Some(_) if policy == MergePolicy::Error => {
+                    return Err(MergeError::Clash { name });
+                }
+                Some(_) if policy == MergePolicy::OuterWins => {}
+                Some((_, outer)) => *outer = capture,
+            }
+        }
+        Ok(self)
+    }
+}
+
+/// Present values by name, as input to `Pattern::build`.
+impl FromIterator<(String, MatchValue)> for Matches {
+    fn from_iter<I: IntoIterator<Item = (String, MatchValue)>>(values: I) -> Self {
+        let captures = values
+            .into_iter()
+            .map(|(name, value)| (name, Capture::Present(value)))
+            .collect();
+        Self { captures }
+    }
+}
+
+impl From<Vec<MatchResult>> for Matches {
+    fn from(results: Vec<MatchResult>) -> Self {
+        let captures = results
+            .into_iter()
+            .filter_map(|result| match result {
+                MatchResult::Matched {
+                    value: MatchValue::Terminus,
+                    ..
+                } => None,
+                MatchResult::Matched {
+                    value,
+                    name: Some(name),
+                    ..
+                }
+                | MatchResult::Alternative { value, name, .. } => {
+                    Some((name, Capture::Present(value)))
+                }
+                MatchResult::Absent { name } => Some((name, Capture::Absent)),
+                MatchResult::Defaulted { value, name } => Some((name, Capture::Default(value))),
+                _ => None,
+            })
+            .collect();
+        Self { captures }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::errors::ValueError;
+    use crate::pattern::Pattern;
+
+    #[test]
+    fn matches_exclude_terminus() {
+        let pattern: Pattern = "/users/:id<integer>/".parse().unwrap();
+        let results = pattern.match_path("/users/5/").unwrap();
+        assert!(results.iter().any(|r| matches!(
+            r,
+            MatchResult::Matched {
+                value: MatchValue::Terminus,
+                ..
+            }
+        )));
+        let matches = Matches::from(results);
+        assert_eq!(matches.len(), 1);
+        assert!(matches
+            .iter()
+            .all(|(_, c)| !c.value().unwrap().is_terminus()));
+
+        let named = Matches::from(vec![MatchResult::Matched {
+            value: MatchValue::Terminus,
+            name: Some("end".to_string()),
+            segment_index: None,
+            span: None,
+        }]);
+        assert!(named.is_empty());
+    }
+    #[test]
+    fn matches_merge_policies() {
+        let outer = Matches::from(
+            "/orgs/:org/:id<integer>"
+                .parse::<Pattern>()
+                .unwrap()
+                .match_path("/orgs/acme/1")
+                .unwrap(),
+        );
+        let inner = Matches::from(
+            "/repos/:id<integer>/:tab?"
+                .parse::<Pattern>()
+                .unwrap()
+                .match_path("/repos/2")
+                .unwrap(),
+        );
+        assert_eq!(
+            outer.clone().merge(inner.clone(), MergePolicy::Error),
+            Err(MergeError::Clash {
+                name: "id".to_string()
+            })
+        );
+        let merged = outer
+            .clone()
+            .merge(inner.clone(), MergePolicy::OuterWins)
+            .unwrap();
+        assert_eq!(merged.value("id"), Some(&MatchValue::Integer(1)));
+        let merged = outer.merge(inner, MergePolicy::InnerWins).unwrap();
+        assert_eq!(merged.value("id"), Some(&MatchValue::Integer(2)));
+        let names: Vec<&str> = merged.iter().map(|(name, _)| name).collect();
+        assert_eq!(names, ["org", "id", "tab"]);
+        assert_eq!(merged.get("tab"), Some(&Capture::Absent));
+    }
+    #[test]
+    fn terminus_conversions_err() {
+        assert_eq!(MatchValue::Terminus.as_str(), None);
+        assert_eq!(MatchValue::Terminus.as_i64(), None);
+        assert_eq!(
+            i64::try_from(MatchValue::Terminus),
+            Err(ValueError::Terminus)
+        );
+        assert_eq!(
+            String::try_from(MatchValue::Integer(5)),
+            Err(ValueError::WrongType {
+                expected: "string",
+                got: "integer"
+            })
+        );
+        assert_eq!(i64::try_from(MatchValue::Integer(5)), Ok(5));
+    }
+}
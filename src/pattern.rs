@@ -0,0 +1,1200 @@
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+use crate::errors::MatchError;
+use crate::matches::Matches;
+use crate::segments::{
+    absent_group, glob_matches, match_repeat, Constraints, MatchResult, MatchValue, SegType,
+    Segment,
+};
+
+/// Limits applied across a whole `match_path` call, independent of any
+/// per-var constraints.
+#[derive(Debug, Clone, Default)]
+pub struct MatchContext {
+    /// Reject any path segment longer than this many bytes.
+    pub max_segment_bytes: Option<usize>,
+    /// Reject paths with more than this many segments, counted up front
+    /// (stopping at the limit) before any segment is matched. Guards
+    /// catch-all and repeat routes against paths of millions of `/`s.
+    pub max_segments: Option<usize>,
+}
+impl MatchContext {
+    fn check_path(&self, path: &str) -> Result<(), MatchError> {
+        if let Some(max) = self.max_segments {
+            if split_path(path).nth(max).is_some() {
+                return Err(MatchError::TooManySegments { max });
+            }
+        }
+        Ok(())
+    }
+    fn check_segment(&self, input: &str) -> Result<(), MatchError> {
+        if let Some(max) = self.max_segment_bytes {
+            if input.len() > max {
+                return Err(MatchError::SegmentTooLong {
+                    max,
+                    len: input.len(),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A declared query parameter, `key=value`, whose value is a var or a static.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryParam {
+    pub key: String,
+    pub value: Segment,
+}
+
+/// What matching does with query parameters the pattern doesn't declare.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ExtraParams {
+    #[default]
+    Ignore,
+    Reject,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Pattern {
+    segments: Vec<Segment>,
+    query: Vec<QueryParam>,
+    extra_params: ExtraParams,
+}
+
+impl Pattern {
+    pub fn new(segments: Vec<Segment>) -> Self {
+        Self {
+            segments,
+            query: Vec::new(),
+            extra_params: ExtraParams::default(),
+        }
+    }
+    /// Declares query parameters, matched in any order after the path. A
+    /// param whose var is optional or has a default may be left out. Only
+    /// patterns that declare params look for a query at all; others match
+    /// a `?` like any other character.
+    pub fn with_query(mut self, query: Vec<QueryParam>) -> Self {
+        self.query = query;
+        self
+    }
+    pub fn with_extra_params(mut self, policy: ExtraParams) -> Self {
+        self.extra_params = policy;
+        self
+    }
+    pub fn segments(&self) -> &[Segment] {
+        &self.segments
+    }
+    pub fn query(&self) -> &[QueryParam] {
+        &self.query
+    }
+    /// Splits `path` at its query string if the pattern declares params.
+    pub(crate) fn split_query<'p>(&self, path: &'p str) -> (&'p str, Option<&'p str>) {
+        if self.query.is_empty() {
+            return (path, None);
+        }
+        match path.split_once('?') {
+            Some((path, query)) => (path, Some(query)),
+            None => (path, Some("")),
+        }
+    }
+    /// Whether the pattern can consume a varying number of path segments,
+    /// or has query params; neither fits a router's segment tree.
+    pub fn is_dynamic(&self) -> bool {
+        !self.query.is_empty()
+            || self.segments.iter().any(|segment| match segment {
+                Segment::Repeat(_)
+                | Segment::Optional(_)
+                | Segment::CatchAll(..)
+                | Segment::Glob(_) => true,
+                Segment::Var(v) => v.is_optional(),
+                _ => false,
+            })
+    }
+    /// Whether the two patterns match exactly the same paths the same way,
+    /// ignoring var names: `/users/:id` and `/users/:user_id` are
+    /// structurally equal, so one would always shadow the other in a router.
+    pub fn structurally_eq(&self, other: &Pattern) -> bool {
+        segments_eq(&self.segments, &other.segments)
+            && self.extra_params == other.extra_params
+            && self.query.len() == other.query.len()
+            && self.query.iter().all(|param| {
+                other.query.iter().any(|p| {
+                    p.key == param.key
+                        && segments_eq(
+                            std::slice::from_ref(&p.value),
+                            std::slice::from_ref(&param.value),
+                        )
+                })
+            })
+    }
+    /// Whether this pattern matches every path `other` does, so a router
+    /// trying it first would leave `other` unreachable. This errs towards
+    /// `false`: it's only `true` when the segments line up one to one and
+    /// each of ours accepts everything the other's does, which an
+    /// unconstrained `string` var does for any var rejecting empty input
+    /// and an unconstrained `number` var does for an unconstrained
+    /// `integer`. Patterns with groups, catch-alls, globs, optional vars or
+    /// query params never cover, nor are covered.
+    pub fn covers(&self, other: &Pattern) -> bool {
+        !self.is_dynamic()
+            && !other.is_dynamic()
+            && self.segments.len() == other.segments.len()
+            && self
+                .segments
+                .iter()
+                .zip(&other.segments)
+                .all(|(a, b)| segment_covers(a, b))
+    }
+    /// A hash that's equal for structurally equal patterns (see
+    /// `structurally_eq`), for use as a cache key. It's FNV-1a over the
+    /// pattern's structure, so unlike a `DefaultHasher` hash it's the same
+    /// from run to run.
+    pub fn structural_hash(&self) -> u64 {
+        let mut hasher = Fnv::default();
+        hash_segments(&self.segments, &mut hasher);
+        // params match in any order, so they're hashed as a sorted set
+        let mut params: Vec<u64> = self
+            .query
+            .iter()
+            .map(|param| {
+                let mut hasher = Fnv::default();
+                param.key.hash(&mut hasher);
+                hash_segments(std::slice::from_ref(&param.value), &mut hasher);
+                hasher.finish()
+            })
+            .collect();
+        params.sort_unstable();
+        params.hash(&mut hasher);
+        (self.extra_params as u8).hash(&mut hasher);
+        hasher.finish()
+    }
+    /// An equivalent pattern with redundancies removed, as programmatic
+    /// construction can leave them, so that it displays, hashes and
+    /// compares in a normal form. It:
+    ///
+    /// - drops empty static segments, such as a doubled `/` leaves, so
+    ///   the result matches the single-`/` form of such paths instead;
+    /// - drops empty optional groups, and unwraps an optional group whose
+    ///   only segment is another optional group;
+    /// - drops var constraints that can't change a match: an empty
+    ///   `strip_prefix`, integer bounds at `i64::MIN` or `i64::MAX`,
+    ///   `clamp` without bounds, a minimum length of 0, `graphemes`
+    ///   without a length, an `allow_empty` that repeats the default for
+    ///   where the var is, and alternatives repeating the var's type or an
+    ///   earlier alternative;
+    /// - sorts and dedups excluded words, units and scripts, whose order
+    ///   doesn't matter.
+    pub fn simplify(&self) -> Pattern {
+        Pattern {
+            segments: simplify_segments(&self.segments),
+            query: self
+                .query
+                .iter()
+                .map(|param| QueryParam {
+                    key: param.key.clone(),
+                    value: match &param.value {
+                        Segment::Var(var) => Segment::Var(var.simplified(true)),
+                        value => value.clone(),
+                    },
+                })
+                .collect(),
+            extra_params: self.extra_params,
+        }
+    }
+    /// Matches `path` and collects its named captures.
+    pub fn captures(&self, path: &str) -> Result<Matches, MatchError> {
+        self.match_path(path).map(Matches::from)
+    }
+    /// Per-segment ranks used to order routes: statics and termini outrank
+    /// vars, which outrank repeated and optional groups, compared left to right. The
+    /// trailing end marker outranks everything, so a pattern that stops
+    /// beats one that could keep consuming segments.
+    pub(crate) fn specificity(&self) -> Vec<u8> {
+        self.segments
+            .iter()
+            .map(|segment| match segment {
+                Segment::Static(_) | Segment::Terminus => 2,
+                Segment::Var(_) | Segment::Glob(_) => 1,
+                Segment::Repeat(_) | Segment::Optional(_) | Segment::CatchAll(..) => 0,
+            })
+            .chain(std::iter::once(3))
+            .collect()
+    }
+    /// A rough relative cost of matching this pattern, for ordering or
+    /// sharding patterns by cheapness. Statics cost the least; dates,
+    /// times, templates and globs the most. The numbers only mean
+    /// anything compared with each other.
+    pub fn estimated_match_cost(&self) -> u32 {
+        self.segments
+            .iter()
+            .chain(self.query.iter().map(|param| &param.value))
+            .map(Segment::cost)
+            .sum()
+    }
+    /// A cheap, allocation-free prefilter: `false` means the path's first
+    /// segment can't match a leading static segment, so `match_path` would
+    /// fail. `true` doesn't guarantee a match.
+    pub fn may_match(&self, path: &str) -> bool {
+        let (path, _) = self.split_query(path);
+        match (self.segments.first(), split_path(path).next()) {
+            (Some(Segment::Static(expected)), Some(first)) => expected == first,
+            _ => true,
+        }
+    }
+    pub fn match_path(&self, path: &str) -> Result<Vec<MatchResult>, MatchError> {
+        self.match_path_with(path, &MatchContext::default())
+    }
+    /// Matches a hostname against a pattern from `Pattern::parse_host`,
+    /// label by label. The host is lowercased first, and a trailing `.`
+    /// ignored. Captures' segment indices are label indices, and their
+    /// spans are offsets into the host.
+    pub fn match_host(&self, host: &str) -> Result<Vec<MatchResult>, MatchError> {
+        let host = host.strip_suffix('.').unwrap_or(host);
+        self.match_path(&host.to_ascii_lowercase().replace('.', "/"))
+    }
+    /// Matches each path independently, keeping results in input order.
+    pub fn match_many(&self, paths: &[&str]) -> Vec<Result<Vec<MatchResult>, MatchError>> {
+        paths.iter().map(|path| self.match_path(path)).collect()
+    }
+    pub fn match_path_with(
+        &self,
+        path: &str,
+        ctx: &MatchContext,
+    ) -> Result<Vec<MatchResult>, MatchError> {
+        let mut results = Vec::new();
+        self.match_path_into_with(path, ctx, &mut results)?;
+        Ok(results)
+    }
+    /// Like `match_path`, but clears `out` and fills it with the results,
+    /// so a hot loop can reuse one buffer instead of allocating a vec per
+    /// call. On error `out` holds whatever had matched so far.
+    pub fn match_path_into(
+        &self,
+        path: &str,
+        out: &mut Vec<MatchResult>,
+    ) -> Result<(), MatchError> {
+        self.match_path_into_with(path, &MatchContext::default(), out)
+    }
+    pub fn match_path_into_with(
+        &self,
+        path: &str,
+        ctx: &MatchContext,
+        out: &mut Vec<MatchResult>,
+    ) -> Result<(), MatchError> {
+        out.clear();
+        let input = path;
+        let (path, query) = self.split_query(path);
+        ctx.check_path(path)?;
+        // reject on a leading static before reserving results, so only the
+        // error itself allocates
+        if let (Some(Segment::Static(expected)), Some(got)) =
+            (self.segments.first(), split_path(path).next())
+        {
+            if expected != got {
+                return Err(MatchError::StaticMismatch {
+                    expected: expected.clone(),
+                    got: got.to_string(),
+                });
+            }
+        }
+        out.reserve(self.segments.len() + self.query.len());
+        let parts = split_path(path).peekable();
+        match_segments(&self.segments, 0, input, parts, ctx, out)?;
+        match query {
+            Some(query) => self.match_query(input, query, ctx, out),
+            None => Ok(()),
+        }
+    }
+    /// Whether `path` fits the pattern's shape: statics, globs and termini
+    /// must match and segment counts must line up, but every var accepts
+    /// any non-empty segment. This skips type validation and constraints
+    /// entirely, so `true` doesn't mean `match_path` would succeed; it's for
+    /// callers that only need to know which route fits structurally, and
+    /// it never allocates. Query strings are ignored.
+    pub fn match_structure(&self, path: &str) -> bool {
+        let (path, _) = self.split_query(path);
+        structure_matches(&self.segments, split_path(path).peekable())
+    }
+    /// Pushes each declared param's captures, in declaration order. When a
+    /// key repeats, its first value is used.
+    fn match_query(
+        &self,
+        input: &str,
+        query: &str,
+        ctx: &MatchContext,
+        out: &mut Vec<MatchResult>,
+    ) -> Result<(), MatchError> {
+        let pairs = || {
+            query
+                .split('&')
+                .filter(|pair| !pair.is_empty())
+                // a bare key's empty value is still a slice of the input,
+                // so its span can be found
+                .map(|pair| pair.split_once('=').unwrap_or((pair, &pair[pair.len()..])))
+        };
+        if self.extra_params == ExtraParams::Reject {
+            if let Some((key, _)) =
+                pairs().find(|(key, _)| !self.query.iter().any(|param| param.key == *key))
+            {
+                return Err(MatchError::UnexpectedParam {
+                    key: key.to_string(),
+                });
+            }
+        }
+        for (i, param) in self.query.iter().enumerate() {
+            let start = out.len();
+            match pairs().find(|(key, _)| *key == param.key) {
+                Some((_, value)) => {
+                    ctx.check_segment(value)?;
+                    match &param.value {
+                        Segment::Var(var) => var.match_query_into(value, out)?,
+                        segment => segment.match_into(value, out)?,
+                    }
+                    set_span(&mut out[start..], span_of(input, &[value]));
+                }
+                None => out.push(param.value.match_absent().ok_or_else(|| {
+                    MatchError::MissingParam {
+                        key: param.key.clone(),
+                    }
+                })??),
+            }
+            set_segment_index(&mut out[start..], self.segments.len() + i);
+        }
+        Ok(())
+    }
+}
+
+/// 64-bit FNV-1a.
+struct Fnv(u64);
+
+impl Default for Fnv {
+    fn default() -> Self {
+        Self(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv {
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 = (self.0 ^ u64::from(b)).wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Hashes what `segments_eq` compares. A var is hashed by its display
+/// form from the `<` on, which covers its type, constraints and default
+/// but not its name.
+fn hash_segments(segments: &[Segment], hasher: &mut Fnv) {
+    segments.len().hash(hasher);
+    for segment in segments {
+        match segment {
+            Segment::Static(s) => (0u8, s).hash(hasher),
+            Segment::Var(v) => {
+                let var = v.to_string();
+                (1u8, &var[var.find('<').unwrap_or(0)..]).hash(hasher);
+            }
+            Segment::Repeat(group) => {
+                2u8.hash(hasher);
+                hash_segments(group, hasher);
+            }
+            Segment::Optional(group) => {
+                3u8.hash(hasher);
+                hash_segments(group, hasher);
+            }
+            Segment::CatchAll(_, checks) => (4u8, checks.no_dots, checks.no_empty).hash(hasher),
+            Segment::Glob(glob) => (5u8, glob).hash(hasher),
+            Segment::Terminus => 6u8.hash(hasher),
+        }
+    }
+}
+
+fn simplify_segments(segments: &[Segment]) -> Vec<Segment> {
+    segments
+        .iter()
+        .filter_map(|segment| match segment {
+            Segment::Static(s) if s.is_empty() => None,
+            Segment::Var(var) => Some(Segment::Var(var.simplified(false))),
+            Segment::Repeat(group) => Some(Segment::Repeat(simplify_segments(group))),
+            Segment::Optional(group) => match simplify_segments(group).as_slice() {
+                [] => None,
+                [inner @ Segment::Optional(_)] => Some(inner.clone()),
+                group => Some(Segment::Optional(group.to_vec())),
+            },
+            segment => Some(segment.clone()),
+        })
+        .collect()
+}
+
+/// Whether `a` accepts every path segment `b` does, for `Pattern::covers`.
+fn segment_covers(a: &Segment, b: &Segment) -> bool {
+    match (a, b) {
+        (Segment::Var(var), Segment::Static(s)) => var.try_match(s).is_ok(),
+        (Segment::Var(var), Segment::Var(other)) => {
+            let any = Constraints::default();
+            segments_eq(std::slice::from_ref(a), std::slice::from_ref(b))
+                || *var.constraints() == any
+                    && match (var.seg_type(), other.seg_type()) {
+                        (SegType::String, _) => other.try_match("").is_err(),
+                        (SegType::Number, SegType::Integer) => *other.constraints() == any,
+                        _ => false,
+                    }
+        }
+        (a, b) => a == b,
+    }
+}
+
+fn segments_eq(a: &[Segment], b: &[Segment]) -> bool {
+    a.len() == b.len()
+        && a.iter().zip(b).all(|pair| match pair {
+            (Segment::Var(a), Segment::Var(b)) => {
+                a.seg_type() == b.seg_type()
+                    && a.constraints() == b.constraints()
+                    && a.is_optional() == b.is_optional()
+                    && a.default() == b.default()
+            }
+            (Segment::Repeat(a), Segment::Repeat(b))
+            | (Segment::Optional(a), Segment::Optional(b)) => segments_eq(a, b),
+            (a, b) => a == b,
+        })
+}
+
+/// `match_segments` for `Pattern::match_structure`, with the same
+/// backtracking over optional groups.
+fn structure_matches(segments: &[Segment], mut parts: Parts<'_>) -> bool {
+    for (i, segment) in segments.iter().enumerate() {
+        match segment {
+            Segment::Repeat(group) => {
+                let mut count = 0;
+                while let Some(part) = parts.next_if(|part| !part.is_empty()) {
+                    if !fits(&group[count % group.len()], part) {
+                        return false;
+                    }
+                    count += 1;
+                }
+                if count % group.len() != 0 {
+                    return false;
+                }
+            }
+            Segment::CatchAll(_, checks) => {
+                let tail = segments.len() - i - 1;
+                let Some(taken) = parts.clone().count().checked_sub(tail) else {
+                    return false;
+                };
+                if !parts.clone().all(|part| checks.allows(part)) {
+                    return false;
+                }
+                parts.by_ref().take(taken).for_each(drop);
+            }
+            Segment::Optional(group) => {
+                let rest = &segments[i + 1..];
+                let mut present = parts.clone();
+                if group
+                    .iter()
+                    .all(|segment| present.next().is_some_and(|part| fits(segment, part)))
+                    && structure_matches(rest, present)
+                {
+                    return true;
+                }
+                return structure_matches(rest, parts);
+            }
+            _ => match parts.next() {
+                Some(part) if !(part.is_empty() && segment.is_optional()) => {
+                    if !fits(segment, part) {
+                        return false;
+                    }
+                }
+                _ if segment.is_optional() => {}
+                _ => return false,
+            },
+        }
+    }
+    parts.next().is_none()
+}
+
+/// Whether a single-part segment fits `part` without parsing it.
+fn fits(segment: &Segment, part: &str) -> bool {
+    match segment {
+        Segment::Static(s) => s == part,
+        Segment::Glob(glob) => glob_matches(glob, part),
+        Segment::Terminus => part.is_empty(),
+        Segment::Var(_) => !part.is_empty(),
+        // groups can't nest
+        Segment::Repeat(_) | Segment::Optional(_) | Segment::CatchAll(..) => false,
+    }
+}
+
+fn set_segment_index(results: &mut [MatchResult], index: usize) {
+    for result in results {
+        if let MatchResult::Matched { segment_index, .. }
+        | MatchResult::Alternative { segment_index, .. } = result
+        {
+            *segment_index = Some(index);
+        }
+    }
+}
+
+fn set_span(results: &mut [MatchResult], new: Option<(usize, usize)>) {
+    for result in results {
+        if let MatchResult::Matched { span, .. } | MatchResult::Alternative { span, .. } = result {
+            *span = new;
+        }
+    }
+}
+
+/// The byte offsets within `input` from the first to the end of the last
+/// of `parts`, which are slices of it. `None` if one isn't.
+fn span_of(input: &str, parts: &[&str]) -> Option<(usize, usize)> {
+    let offset = |part: &str| {
+        (part.as_ptr() as usize)
+            .checked_sub(input.as_ptr() as usize)
+            .filter(|&offset| offset + part.len() <= input.len())
+    };
+    let (first, last) = (parts.first()?, parts.last()?);
+    Some((offset(first)?, offset(last)? + last.len()))
+}
+
+type Parts<'p> = std::iter::Peekable<std::str::Split<'p, char>>;
+
+/// Matches `segments` against the remaining `parts`, which must all be
+/// consumed. An optional group is first tried present and then absent, so
+/// each one doubles the worst-case work; the parser caps how many a pattern
+/// may have. When both attempts fail, the error from the absent attempt is
+/// returned. `first` is the pattern position of `segments[0]`, used to
+/// set each capture's `segment_index`, and `parts` are slices of `input`,
+/// used to set its `span`.
+fn match_segments(
+    segments: &[Segment],
+    first: usize,
+    input: &str,
+    mut parts: Parts<'_>,
+    ctx: &MatchContext,
+    results: &mut Vec<MatchResult>,
+) -> Result<(), MatchError> {
+    for (i, segment) in segments.iter().enumerate() {
+        let start = results.len();
+        match segment {
+            Segment::Repeat(group) => {
+                let mut consumed = Vec::new();
+                while let Some(part) = parts.next_if(|part| !part.is_empty()) {
+                    ctx.check_segment(part)?;
+                    consumed.push(part);
+                }
+                results.push(match_repeat(group, &consumed)?);
+                set_span(&mut results[start..], span_of(input, &consumed));
+            }
+            Segment::CatchAll(name, checks) => {
+                // the segments after a catch-all each take exactly one part
+                let tail = segments.len() - i - 1;
+                let taken = parts.clone().count().checked_sub(tail);
+                let taken = taken.ok_or(MatchError::MissingSegment)?;
+                let rest: Vec<&str> = parts.clone().collect();
+                rest.iter().try_for_each(|part| checks.check(part))?;
+                for part in parts.by_ref().take(taken) {
+                    ctx.check_segment(part)?;
+                }
+                results.push(MatchResult::Matched {
+                    value: MatchValue::String(rest.join("/")),
+                    name: name.clone(),
+                    segment_index: None,
+                    span: span_of(input, &rest),
+                });
+            }
+            Segment::Optional(group) => {
+                let rest = &segments[i + 1..];
+                let checkpoint = results.len();
+                let mut present = parts.clone();
+                let attempt = group
+                    .iter()
+                    .try_for_each(|segment| {
+                        let part = present.next().ok_or(MatchError::MissingSegment)?;
+                        ctx.check_segment(part)?;
+                        let start = results.len();
+                        segment.match_into(part, results)?;
+                        set_span(&mut results[start..], span_of(input, &[part]));
+                        Ok(())
+                    })
+                    .and_then(|()| {
+                        set_segment_index(&mut results[checkpoint..], first + i);
+                        match_segments(rest, first + i + 1, input, present, ctx, results)
+                    });
+                if attempt.is_ok() {
+                    return Ok(());
+                }
+                results.truncate(checkpoint);
+                results.extend(absent_group(group));
+                return match_segments(rest, first + i + 1, input, parts, ctx, results);
+            }
+            _ => match parts.next() {
+                // an empty part (from a trailing slash) counts as absent for optional segments
+                Some(part) if !(part.is_empty() && segment.is_optional()) => {
+                    ctx.check_segment(part)?;
+                    segment.match_into(part, results)?;
+                    set_span(&mut results[start..], span_of(input, &[part]));
+                }
+                _ => results.push(segment.match_absent().ok_or(MatchError::MissingSegment)??),
+            },
+        }
+        set_segment_index(&mut results[start..], first + i);
+    }
+    if let Some(rest) = parts.next() {
+        return Err(MatchError::TrailingInput {
+            got: rest.to_string(),
+        });
+    }
+    Ok(())
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for segment in &self.segments {
+            write!(f, "/{segment}")?;
+        }
+        for (i, param) in self.query.iter().enumerate() {
+            let sep = if i == 0 { '?' } else { '&' };
+            write!(f, "{sep}{}={}", param.key, param.value)?;
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn split_path(path: &str) -> std::str::Split<'_, char> {
+    path.strip_prefix('/').unwrap_or(path).split('/')
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::segments::{SegType, Var};
+
+    #[test]
+    fn pattern_match_path_ok() {
+        let pattern: Pattern = "/users/:id<number>".parse().unwrap();
+        let result = pattern.match_path("/users/5").unwrap();
+        assert_eq!(
+            result[1],
+            MatchResult::Matched {
+                value: MatchValue::Number(5.0),
+                name: Some("id".to_string()),
+                segment_index: Some(1),
+                span: Some((7, 8))
+            }
+        );
+    }
+    #[test]
+    fn pattern_match_path_leading_static_err() {
+        let pattern: Pattern = "/users/:id<number>".parse().unwrap();
+        assert!(!pattern.may_match("/posts/5"));
+        assert!(pattern.may_match("/users/x"));
+        assert_eq!(
+            pattern.match_path("/posts/5"),
+            Err(MatchError::StaticMismatch {
+                expected: "users".to_string(),
+                got: "posts".to_string()
+            })
+        );
+    }
+    #[test]
+    fn pattern_match_many_keeps_order() {
+        let pattern: Pattern = "/users/:id<number>".parse().unwrap();
+        let results = pattern.match_many(&["/users/1", "/users/bob", "/users/3"]);
+        assert_eq!(results.len(), 3);
+        assert!(results[0].is_ok());
+        assert_eq!(
+            results[1],
+            Err(MatchError::InvalidValue {
+                expected: crate::segments::SegType::Number,
+                got: "bob".to_string()
+            })
+        );
+        assert_eq!(
+            results[2].as_ref().unwrap()[1],
+            MatchResult::Matched {
+                value: MatchValue::Number(3.0),
+                name: Some("id".to_string()),
+                segment_index: Some(1),
+                span: Some((7, 8))
+            }
+        );
+    }
+    #[test]
+    fn pattern_match_path_into_reuses_buffer() {
+        let pattern: Pattern = "/users/:id<number>".parse().unwrap();
+        let mut results = Vec::new();
+        pattern.match_path_into("/users/1", &mut results).unwrap();
+        let capacity = results.capacity();
+        pattern.match_path_into("/users/2", &mut results).unwrap();
+        assert_eq!(results, pattern.match_path("/users/2").unwrap());
+        assert_eq!(results.capacity(), capacity);
+        assert!(pattern.match_path_into("/posts/2", &mut results).is_err());
+        assert!(results.is_empty());
+    }
+    #[test]
+    fn pattern_match_query_params() {
+        let pattern: Pattern = "/search?q=:q&page=:p<number=1>&sort=:s?".parse().unwrap();
+        assert_eq!(
+            pattern.to_string(),
+            "/search?q=:q<string>&page=:p<number=1>&sort=:s<string>?"
+        );
+        let named = |value, name: &str, index, span| MatchResult::Matched {
+            value,
+            name: Some(name.to_string()),
+            segment_index: Some(index),
+            span,
+        };
+        // reordered, with an undeclared extra
+        let results = pattern
+            .match_path("/search?utm=x&sort=new&page=3&q=rust")
+            .unwrap();
+        assert_eq!(
+            results[1..],
+            [
+                named(
+                    MatchValue::String("rust".to_string()),
+                    "q",
+                    1,
+                    Some((32, 36))
+                ),
+                named(MatchValue::Number(3.0), "p", 2, Some((28, 29))),
+                named(
+                    MatchValue::String("new".to_string()),
+                    "s",
+                    3,
+                    Some((19, 22))
+                ),
+            ]
+        );
+        // defaulted and absent
+        let results = pattern.match_path("/search?q=rust").unwrap();
+        assert_eq!(
+            results[2..],
+            [
+                MatchResult::Defaulted {
+                    value: MatchValue::Number(1.0),
+                    name: "p".to_string()
+                },
+                MatchResult::Absent {
+                    name: "s".to_string()
+                },
+            ]
+        );
+        assert_eq!(
+            pattern.match_path("/search?page=2"),
+            Err(MatchError::MissingParam {
+                key: "q".to_string()
+            })
+        );
+        assert!(pattern.match_path("/search").is_err());
+        let strict = pattern.with_extra_params(ExtraParams::Reject);
+        assert_eq!(
+            strict.match_path("/search?q=rust&utm=x"),
+            Err(MatchError::UnexpectedParam {
+                key: "utm".to_string()
+            })
+        );
+        for bad in ["/a?q=", "/a?q=:x&q=:y", "/a?q=:x&", "/a?=1"] {
+            assert!(bad.parse::<Pattern>().is_err(), "{bad}");
+        }
+        // optional vars and groups keep their `?`
+        let pattern: Pattern = "/a/(b/)?:x?".parse().unwrap();
+        assert!(pattern.query().is_empty());
+    }
+    #[test]
+    fn pattern_match_structure_skips_types() {
+        let pattern: Pattern = "/users/:id<number>/posts/:day<date>/".parse().unwrap();
+        assert!(pattern.match_structure("/users/5/posts/2021-01-01/"));
+        assert!(pattern.match_structure("/users/bob/posts/soon"));
+        assert!(pattern.match_path("/users/bob/posts/soon").is_err());
+        for path in [
+            "/users/5",
+            "/users//posts/x",
+            "/users/5/posts/x/y",
+            "/u/5/posts/x",
+        ] {
+            assert!(!pattern.match_structure(path), "{path}");
+        }
+        let pattern: Pattern = "/api/(v:version<number>/)?f/(:k/:v)*".parse().unwrap();
+        assert!(pattern.match_structure("/api/v1/f/a/b"));
+        assert!(pattern.match_structure("/api/f"));
+        assert!(!pattern.match_structure("/api/f/a"));
+        let pattern: Pattern = "/assets/**/*.css".parse().unwrap();
+        assert!(pattern.match_structure("/assets/a/b/site.css"));
+        assert!(!pattern.match_structure("/assets/a/b/site.js"));
+    }
+    #[test]
+    fn pattern_match_max_segments() {
+        let pattern: Pattern = "/files/**path".parse().unwrap();
+        let ctx = MatchContext {
+            max_segments: Some(4),
+            ..Default::default()
+        };
+        assert!(pattern.match_path_with("/files/a/b/c", &ctx).is_ok());
+        assert_eq!(
+            pattern.match_path_with("/files/a/b/c/d", &ctx),
+            Err(MatchError::TooManySegments { max: 4 })
+        );
+        let huge = "/files".to_string() + &"/a".repeat(1_000_000);
+        assert_eq!(
+            pattern.match_path_with(&huge, &ctx),
+            Err(MatchError::TooManySegments { max: 4 })
+        );
+    }
+    #[test]
+    fn pattern_structural_hash() {
+        let hash = |s: &str| s.parse::<Pattern>().unwrap().structural_hash();
+        assert_eq!(
+            hash("/users/:id<integer>/posts/:p?sort=:s&q=:q"),
+            hash("/users/:user_id<integer>/posts/:post?q=:query&sort=:order")
+        );
+        assert_ne!(hash("/users/:id<integer>"), hash("/users/:id<number>"));
+        assert_ne!(
+            hash("/users/:id<integer(1..10)>"),
+            hash("/users/:id<integer(1..20)>")
+        );
+        assert_ne!(hash("/users/:id"), hash("/users/id"));
+    }
+    #[test]
+    fn pattern_match_spans() {
+        let pattern: Pattern = "/users/:id<integer>/posts/:day<date>?q=:q".parse().unwrap();
+        let path = "/users/42/posts/2021-01-02?q=rust";
+        let results = pattern.match_path(path).unwrap();
+        let spans: Vec<_> = results.iter().filter_map(MatchResult::span).collect();
+        assert_eq!(spans, [(1, 6), (7, 9), (10, 15), (16, 26), (29, 33)]);
+        assert_eq!(&path[7..9], "42");
+        assert_eq!(&path[16..26], "2021-01-02");
+        let var = Var::try_from("id<integer>").unwrap();
+        assert_eq!(var.try_match("42").unwrap().span(), None);
+    }
+    #[test]
+    fn pattern_match_bare_query_key_span() {
+        // `q` with no `=` has an empty value, spanning nothing after the key
+        let pattern: Pattern = "/search?q=:q".parse().unwrap();
+        let results = pattern.match_path("/search?q").unwrap();
+        let spans: Vec<_> = results.iter().filter_map(MatchResult::span).collect();
+        assert_eq!(spans, [(1, 7), (9, 9)]);
+    }
+    #[test]
+    fn pattern_match_alternative_span() {
+        let pattern: Pattern = "/a/:v<integer|date>".parse().unwrap();
+        let path = "/a/2021-01-02";
+        let results = pattern.match_path(path).unwrap();
+        let result = results
+            .iter()
+            .find(|result| result.matched_type().is_some())
+            .unwrap();
+        assert_eq!(result.matched_type(), Some(SegType::Date));
+        assert_eq!(result.segment_index(), Some(1));
+        assert_eq!(result.span(), Some((3, 13)));
+        assert_eq!(&path[3..13], "2021-01-02");
+    }
+    #[test]
+    fn pattern_covers() {
+        let covers = |a: &str, b: &str| {
+            a.parse::<Pattern>()
+                .unwrap()
+                .covers(&b.parse::<Pattern>().unwrap())
+        };
+        assert!(covers("/users/:id", "/users/new"));
+        assert!(covers("/users/:id", "/users/:n<integer(1..10)>"));
+        assert!(covers("/items/:n<number>", "/items/:i<integer>"));
+        assert!(covers("/users/:id<integer>", "/users/:uid<integer>"));
+        assert!(!covers("/users/:id<integer>", "/users/new"));
+        assert!(!covers("/users/:n<integer(1..10)>", "/users/:id"));
+        assert!(!covers("/users/:id", "/users/:id/posts"));
+        assert!(!covers("/users/:id", "/users/:s<string(allow_empty)>"));
+        assert!(!covers("/files/**", "/files/a"));
+    }
+    #[test]
+    fn pattern_simplify() {
+        let mut id = Var::new("id".to_string(), SegType::Integer);
+        let constraints = id.constraints_mut();
+        constraints.int_min = Some(i64::MIN);
+        constraints.int_max = Some(i64::MAX);
+        constraints.clamp = true;
+        constraints.strip_prefix = Some(String::new());
+        constraints.alternatives = vec![SegType::Integer, SegType::Uuid, SegType::Uuid];
+        let mut q = Var::new("q".to_string(), SegType::String);
+        q.constraints_mut().allow_empty = Some(true);
+        let mut slug = Var::new("slug".to_string(), SegType::String);
+        slug.constraints_mut().excluded =
+            vec!["new".to_string(), "edit".to_string(), "new".to_string()];
+        slug.constraints_mut().allow_empty = Some(false);
+        let pattern = Pattern::new(vec![
+            Segment::Static("users".to_string()),
+            Segment::Static(String::new()),
+            Segment::Var(id),
+            Segment::Optional(vec![Segment::Optional(vec![
+                Segment::Static("posts".to_string()),
+                Segment::Var(slug),
+            ])]),
+            Segment::Optional(vec![Segment::Static(String::new())]),
+        ])
+        .with_query(vec![QueryParam {
+            key: "q".to_string(),
+            value: Segment::Var(q),
+        }]);
+        let expected: Pattern =
+            "/users/:id<integer|uuid>/(posts/:slug<string(not=edit,new)>)??q=:q"
+                .parse()
+                .unwrap();
+        assert_eq!(pattern.simplify(), expected);
+        assert_eq!(expected.simplify(), expected);
+    }
+    #[test]
+    fn pattern_empty_strings_by_context() {
+        let pattern: Pattern = "/search/:scope?q=:q".parse().unwrap();
+        assert!(pattern.match_path("/search/all?q=").is_ok());
+        assert_eq!(
+            pattern.match_path("/search/?q=rust"),
+            Err(MatchError::InvalidValue {
+                expected: SegType::String,
+                got: String::new()
+            })
+        );
+        let pattern: Pattern =
+            "/search/:scope<string(allow_empty)>?q=:q<string(allow_empty=false)>"
+                .parse()
+                .unwrap();
+        assert_eq!(
+            pattern.to_string(),
+            "/search/:scope<string(allow_empty)>?q=:q<string(allow_empty=false)>"
+        );
+        assert!(pattern.match_path("/search/?q=rust").is_ok());
+        assert!(pattern.match_path("/search/all?q=").is_err());
+    }
+    #[test]
+    fn pattern_estimated_match_cost() {
+        let cost = |s: &str| s.parse::<Pattern>().unwrap().estimated_match_cost();
+        let all_static = cost("/a/b/c");
+        assert!(cost("/a/b/:v<template(thumb-{id}.{ext})>") > all_static);
+        assert!(cost("/a/b/:d<date>") > cost("/a/b/:n<integer>"));
+        assert!(cost("/a/b/:n<integer>") > all_static);
+        assert!(cost("/a/b/c?page=:p<integer>") > all_static);
+    }
+    #[test]
+    fn pattern_match_catch_all_checks() {
+        let pattern: Pattern = "/files/**path(no_dots,no_empty)".parse().unwrap();
+        assert_eq!(pattern.to_string(), "/files/**path(no_dots,no_empty)");
+        let results = pattern.match_path("/files/docs/a.txt").unwrap();
+        assert_eq!(
+            results[1],
+            MatchResult::Matched {
+                value: MatchValue::String("docs/a.txt".to_string()),
+                name: Some("path".to_string()),
+                segment_index: Some(1),
+                span: Some((7, 17))
+            }
+        );
+        assert_eq!(
+            pattern.match_path("/files/docs/../../etc/passwd"),
+            Err(MatchError::InvalidRestPart {
+                got: "..".to_string()
+            })
+        );
+        assert!(pattern.match_path("/files/docs//a.txt").is_err());
+        assert!(!pattern.match_structure("/files/./a.txt"));
+        // unchecked catch-alls take anything
+        let pattern: Pattern = "/files/**path".parse().unwrap();
+        assert!(pattern.match_path("/files/docs/../a.txt").is_ok());
+    }
+    #[test]
+    fn pattern_match_path_trailing_err() {
+        let pattern: Pattern = "/users/:id<number>".parse().unwrap();
+        let result = pattern.match_path("/users/5/posts");
+        assert_eq!(
+            result,
+            Err(MatchError::TrailingInput {
+                got: "posts".to_string()
+            })
+        );
+    }
+    #[test]
+    fn pattern_match_path_max_bytes_err() {
+        let pattern: Pattern = "/users/:name<string(max_bytes=8)>".parse().unwrap();
+        let long = "a".repeat(10_000);
+        let result = pattern.match_path(&format!("/users/{long}"));
+        assert_eq!(
+            result,
+            Err(MatchError::SegmentTooLong {
+                max: 8,
+                len: 10_000
+            })
+        );
+    }
+    #[test]
+    fn pattern_match_repeat_pairs() {
+        let pattern: Pattern = "/filter/(:k<string>/:v<string>)*".parse().unwrap();
+        let result = pattern.match_path("/filter/color/red/size/xl").unwrap();
+        let pair = |k: &str, v: &str| {
+            MatchValue::List(vec![
+                MatchValue::String(k.to_string()),
+                MatchValue::String(v.to_string()),
+            ])
+        };
+        assert_eq!(
+            result[1],
+            MatchResult::Matched {
+                value: MatchValue::List(vec![pair("color", "red"), pair("size", "xl")]),
+                name: None,
+                segment_index: Some(1),
+                span: Some((8, 25))
+            }
+        );
+        assert!(pattern.match_path("/filter/color/red/size").is_err());
+    }
+    #[test]
+    fn pattern_match_template_captures() {
+        let pattern: Pattern = "/img/:v<template(thumb-{id}.{ext})>".parse().unwrap();
+        let result = pattern.match_path("/img/thumb-42.jpg").unwrap();
+        let names: Vec<_> = result
+            .iter()
+            .filter_map(|r| match r {
+                MatchResult::Matched {
+                    value: MatchValue::String(v),
+                    name: Some(n),
+                    ..
+                } => Some((n.as_str(), v.as_str())),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(
+            names,
+            vec![("v", "thumb-42.jpg"), ("id", "42"), ("ext", "jpg")]
+        );
+    }
+    #[test]
+    fn pattern_match_segment_index_groups_captures() {
+        let pattern: Pattern = "/img/:v<template(thumb-{id}.{ext})>/:size<number>"
+            .parse()
+            .unwrap();
+        let result = pattern.match_path("/img/thumb-42.jpg/2").unwrap();
+        let indices: Vec<_> = result.iter().map(MatchResult::segment_index).collect();
+        assert_eq!(indices, [Some(0), Some(1), Some(1), Some(1), Some(2)]);
+        let var = Var::try_from("v<template(thumb-{id}.{ext})>").unwrap();
+        assert_eq!(var.try_match("thumb-42.jpg").unwrap().segment_index(), None);
+    }
+    #[test]
+    fn pattern_match_optional_trailing_vars() {
+        use crate::matches::Capture;
+
+        let pattern: Pattern = "/posts/:tag?/:page<integer=1>".parse().unwrap();
+        let captures = pattern.captures("/posts/rust/3").unwrap();
+        assert_eq!(
+            captures.get("tag"),
+            Some(&Capture::Present(MatchValue::String("rust".to_string())))
+        );
+        assert_eq!(
+            captures.get("page"),
+            Some(&Capture::Present(MatchValue::Integer(3)))
+        );
+
+        let captures = pattern.captures("/posts").unwrap();
+        assert_eq!(captures.get("tag"), Some(&Capture::Absent));
+        assert_eq!(
+            captures.get("page"),
+            Some(&Capture::Default(MatchValue::Integer(1)))
+        );
+        assert_eq!(captures.value("page"), Some(&MatchValue::Integer(1)));
+        assert_eq!(captures.value("tag"), None);
+
+        let captures = pattern.captures("/posts/rust/").unwrap();
+        assert_eq!(
+            captures.get("page"),
+            Some(&Capture::Default(MatchValue::Integer(1)))
+        );
+    }
+    #[test]
+    fn pattern_match_optional_group() {
+        use crate::matches::Capture;
+
+        let pattern: Pattern = "/api/(v:version<number>/)?users".parse().unwrap();
+        assert_eq!(
+            pattern.to_string(),
+            "/api/(:version<number(strip_prefix=v)>)?/users"
+        );
+        let captures = pattern.captures("/api/v2/users").unwrap();
+        assert_eq!(
+            captures.get("version"),
+            Some(&Capture::Present(MatchValue::Number(2.0)))
+        );
+        let captures = pattern.captures("/api/users").unwrap();
+        assert_eq!(captures.get("version"), Some(&Capture::Absent));
+        assert!(pattern.match_path("/api/v2").is_err());
+        assert!(pattern.match_path("/api/vx/users").is_err());
+    }
+    #[test]
+    fn pattern_match_optional_group_backtracks() {
+        // the group could swallow `users`, but then the static would fail
+        let pattern: Pattern = "/(:lang/:region)?/users/:id".parse().unwrap();
+        let captures = pattern.captures("/users/5").unwrap();
+        assert_eq!(captures.value("lang"), None);
+        assert_eq!(
+            captures.value("id"),
+            Some(&MatchValue::String("5".to_string()))
+        );
+        let captures = pattern.captures("/en/us/users/5").unwrap();
+        assert_eq!(
+            captures.value("region"),
+            Some(&MatchValue::String("us".to_string()))
+        );
+    }
+    #[test]
+    fn pattern_match_catch_all_glob() {
+        let pattern: Pattern = "/assets/**path/*.css".parse().unwrap();
+        let captures = pattern.captures("/assets/css/vendor/site.css").unwrap();
+        assert_eq!(
+            captures.value("path"),
+            Some(&MatchValue::String("css/vendor/site.css".to_string()))
+        );
+        let captures = pattern.captures("/assets/site.css").unwrap();
+        assert_eq!(
+            captures.value("path"),
+            Some(&MatchValue::String("site.css".to_string()))
+        );
+        assert_eq!(
+            pattern.match_path("/assets/css/site.js"),
+            Err(MatchError::StaticMismatch {
+                expected: "*.css".to_string(),
+                got: "site.js".to_string()
+            })
+        );
+        assert_eq!(
+            pattern.match_path("/assets"),
+            Err(MatchError::MissingSegment)
+        );
+        let explanation = pattern.explain("/assets/css/site.js");
+        assert_eq!(explanation.failure_index(), Some(2));
+    }
+    #[test]
+    fn pattern_structurally_eq_ignores_names() {
+        let parse = |s: &str| s.parse::<Pattern>().unwrap();
+        assert!(parse("/users/:id<number>").structurally_eq(&parse("/users/:uid<number>")));
+        assert!(parse("/(:k/:v)*").structurally_eq(&parse("/(:a/:b)*")));
+        assert!(!parse("/users/:id<number>").structurally_eq(&parse("/users/:id<integer>")));
+        assert!(!parse("/users/:id").structurally_eq(&parse("/users/:id/")));
+        assert!(!parse("/users/:id").structurally_eq(&parse("/users/:id?")));
+    }
+    #[test]
+    fn pattern_display_roundtrip() {
+        let source = "/users/:id<number>/:name<string(max_bytes=8)>/(:k<string>/:v<date>)*/";
+        let pattern: Pattern = source.parse().unwrap();
+        assert_eq!(pattern.to_string(), source);
+        let source = "/posts/:tag<string>?/:page<integer=1>";
+        let pattern: Pattern = source.parse().unwrap();
+        assert_eq!(pattern.to_string(), source);
+    }
+    #[test]
+    fn pattern_match_context_max_segment_bytes_err() {
+        let pattern: Pattern = "/users/:name".parse().unwrap();
+        let ctx = MatchContext {
+            max_segment_bytes: Some(16),
+            ..Default::default()
+        };
+        let result = pattern.match_path_with(&format!("/users/{}", "a".repeat(17)), &ctx);
+        assert_eq!(result, Err(MatchError::SegmentTooLong { max: 16, len: 17 }));
+    }
+}
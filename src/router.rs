@@ -0,0 +1,1324 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+
+use crate::config::source_specificity;
+use crate::errors::RouterError;
+use crate::pattern::{split_path, Pattern};
+use crate::segments::{MatchResult, MatchValue, RestChecks, Segment, Var};
+
+#[derive(Debug, Clone)]
+enum RoutePattern {
+    Parsed(Pattern),
+    /// Parsed on first use; `None` once parsing has failed, after which
+    /// the route never matches.
+    Lazy {
+        source: String,
+        parsed: OnceLock<Option<Pattern>>,
+    },
+}
+
+#[derive(Debug)]
+struct Route<T, K> {
+    id: RouteId,
+    pattern: RoutePattern,
+    key: K,
+    /// Added with `Router::mount`, so only tried once every other route
+    /// has failed.
+    mount: bool,
+    /// Added with `Router::add_host`: the host must match this too.
+    host: Option<Pattern>,
+    specificity: Vec<u8>,
+    value: T,
+    hits: AtomicU64,
+}
+
+impl<T: Clone, K: Clone> Clone for Route<T, K> {
+    fn clone(&self) -> Self {
+        Self {
+            id: self.id,
+            pattern: self.pattern.clone(),
+            key: self.key.clone(),
+            mount: self.mount,
+            host: self.host.clone(),
+            specificity: self.specificity.clone(),
+            value: self.value.clone(),
+            hits: AtomicU64::new(self.hits.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+/// Opt-in match counters, updated with relaxed atomics so matching stays
+/// `&self`. When disabled, nothing is counted.
+#[derive(Debug, Default)]
+struct Counters {
+    enabled: bool,
+    attempts: AtomicU64,
+}
+
+impl Clone for Counters {
+    fn clone(&self) -> Self {
+        Self {
+            enabled: self.enabled,
+            attempts: AtomicU64::new(self.attempts.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+impl Counters {
+    fn record<T, K>(&self, matched: Option<&Route<T, K>>) {
+        if self.enabled {
+            self.attempts.fetch_add(1, Ordering::Relaxed);
+            if let Some(route) = matched {
+                route.hits.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+    }
+    fn stats<T, K>(&self, routes: &[Route<T, K>]) -> Option<RouterStats> {
+        self.enabled.then(|| RouterStats {
+            attempts: self.attempts.load(Ordering::Relaxed),
+            routes: routes
+                .iter()
+                .map(|route| RouteStats {
+                    pattern: route.source(),
+                    hits: route.hits.load(Ordering::Relaxed),
+                })
+                .collect(),
+        })
+    }
+}
+
+/// A snapshot of a router's counters, with routes in match-priority order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouterStats {
+    pub attempts: u64,
+    pub routes: Vec<RouteStats>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RouteStats {
+    pub pattern: String,
+    pub hits: u64,
+}
+
+/// A route's handle, returned when it's added. Ids count up from 0 in the
+/// order routes are added, so a router rebuilt by the same sequence of
+/// calls hands out the same ids. A duplicate replaced under
+/// `DuplicatePolicy::Replace` keeps the id of the route it replaced, and
+/// compiling or cloning a router keeps every id. Ids from one router mean
+/// nothing to another.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct RouteId(pub usize);
+
+#[derive(Debug, PartialEq)]
+pub struct RouteMatch<'a, T> {
+    pub id: RouteId,
+    pub pattern: &'a Pattern,
+    pub value: &'a T,
+    pub captures: Vec<MatchResult>,
+    /// For a route added with `Router::mount`, the rest of the path after
+    /// the prefix, with a leading `/`, ready to match against a nested
+    /// router. `/admin` matching `/admin/users/5` leaves `/users/5`.
+    pub remainder: Option<String>,
+}
+
+/// A `RouteMatch` that owns its parts, for storing or handing across API
+/// boundaries. `index` is the route's position in match-priority order,
+/// the same order `RouterStats::routes` uses.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OwnedMatch<T> {
+    pub index: usize,
+    pub id: RouteId,
+    pub value: T,
+    pub captures: Vec<MatchResult>,
+    /// See `RouteMatch::remainder`.
+    pub remainder: Option<String>,
+}
+
+/// What `Router::add` does with a pattern structurally equal to one
+/// already added (see `Pattern::structurally_eq`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicatePolicy {
+    /// Swap in the new value, keeping the existing route's id, position
+    /// and hits.
+    #[default]
+    Replace,
+    /// Refuse the new route.
+    Error,
+    /// Keep both; the earlier one shadows the later.
+    Allow,
+}
+
+/// Routes are kept ordered by specificity (statics beat vars, left to
+/// right), with ties going to whichever was added first, and the first
+/// matching route wins.
+///
+/// Mounted prefixes (see `mount`) are a fallback: they're tried only once
+/// every other route has failed, and the most specific prefix wins, so a
+/// mount at `/static/css` beats one at `/static` for `/static/css/a.css`.
+///
+/// Routes can also carry a key, such as an HTTP method, that a match must
+/// equal as well; `K` defaults to `()` for routing on paths alone. Keyed
+/// routers are built with `keyed` and use the `_keyed` methods.
+#[derive(Debug, Clone)]
+pub struct Router<T, K = ()> {
+    routes: Vec<Route<T, K>>,
+    counters: Counters,
+    duplicates: DuplicatePolicy,
+    reject_shadowed: bool,
+    next_id: usize,
+}
+
+impl<T, K> Default for Router<T, K> {
+    fn default() -> Self {
+        Self {
+            routes: Vec::new(),
+            counters: Counters::default(),
+            duplicates: DuplicatePolicy::default(),
+            reject_shadowed: false,
+            next_id: 0,
+        }
+    }
+}
+
+impl<T> Router<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// A router that counts match attempts and per-route hits, readable via `stats`.
+    pub fn with_stats() -> Self {
+        Self::keyed_with_stats()
+    }
+    /// Adds a route, returning its id (see `RouteId`). A duplicate under
+    /// `DuplicatePolicy::Replace` gets the id of the route it replaced.
+    ///
+    /// # Panics
+    ///
+    /// Under `DuplicatePolicy::Error`, if the pattern is a duplicate, or
+    /// with `reject_shadowed`, if it shadows or is shadowed by another
+    /// route; use `try_add` to handle those instead.
+    pub fn add(&mut self, pattern: Pattern, value: T) -> RouteId {
+        self.add_keyed((), pattern, value)
+    }
+    /// Like `add`, but returns an error for a duplicate under
+    /// `DuplicatePolicy::Error` or a shadowed route under
+    /// `reject_shadowed`. Only eagerly added routes are compared.
+    pub fn try_add(&mut self, pattern: Pattern, value: T) -> Result<RouteId, RouterError> {
+        self.try_add_keyed((), pattern, value)
+    }
+    /// Mounts `value` at `prefix`, matching any path that starts with it:
+    /// the captures are the prefix's, followed by the rest of the path as
+    /// an unnamed string. Duplicates are handled as `add` handles them.
+    ///
+    /// # Panics
+    ///
+    /// If `prefix` ends in something that can't be followed by more path
+    /// (a repeat group, a catch-all or an optional var), or, under
+    /// `DuplicatePolicy::Error`, if it's already mounted; use `try_mount`
+    /// to handle those instead.
+    pub fn mount(&mut self, prefix: Pattern, value: T) -> RouteId {
+        self.mount_keyed((), prefix, value)
+    }
+    /// Like `mount`, but returns an error instead of panicking.
+    pub fn try_mount(&mut self, prefix: Pattern, value: T) -> Result<RouteId, RouterError> {
+        self.try_mount_keyed((), prefix, value)
+    }
+    /// Adds a route whose pattern is parsed the first time a match reaches
+    /// it rather than up front, which keeps startup cheap for large tables
+    /// of rarely hit routes. The parsed pattern is cached, and is safe to
+    /// share across threads. A source that fails to parse never matches.
+    /// Compiling the router parses every lazy route. Lazy routes are never
+    /// treated as duplicates.
+    pub fn add_lazy(&mut self, source: impl Into<String>, value: T) -> RouteId {
+        self.add_lazy_keyed((), source, value)
+    }
+    /// Adds a route that also requires the host to match `host`, a pattern
+    /// from `Pattern::parse_host`; see `match_host_path`. Routes added
+    /// without a host match any host. Duplicates are handled as `add`
+    /// handles them, and must have structurally equal hosts too.
+    ///
+    /// # Panics
+    ///
+    /// Under `DuplicatePolicy::Error`, if the route is a duplicate, or
+    /// with `reject_shadowed`, if it shadows or is shadowed by another
+    /// route; use `try_add_host` to handle those instead.
+    pub fn add_host(&mut self, host: Pattern, pattern: Pattern, value: T) -> RouteId {
+        self.add_host_keyed((), host, pattern, value)
+    }
+    /// Like `add_host`, but returns an error for a duplicate or shadowed
+    /// route.
+    pub fn try_add_host(
+        &mut self,
+        host: Pattern,
+        pattern: Pattern,
+        value: T,
+    ) -> Result<RouteId, RouterError> {
+        self.try_add_host_keyed((), host, pattern, value)
+    }
+    pub fn match_path(&self, path: &str) -> Option<RouteMatch<'_, T>> {
+        self.match_keyed(&(), path)
+    }
+    /// Matches a request's host and path together. Routes added with
+    /// `add_host` are only considered when their host pattern matches,
+    /// and `match_path` never considers them. A host route beats a
+    /// hostless one with an equally specific path. The host's captures
+    /// follow the path's.
+    pub fn match_host_path(&self, host: &str, path: &str) -> Option<RouteMatch<'_, T>> {
+        self.match_host_keyed(&(), host, path)
+    }
+    /// Like `match_path`, but clones the route's value so the result
+    /// doesn't borrow the router.
+    pub fn match_owned(&self, path: &str) -> Option<OwnedMatch<T>>
+    where
+        T: Clone,
+    {
+        self.match_owned_keyed(&(), path)
+    }
+}
+
+impl<T, K: PartialEq> Router<T, K> {
+    /// An empty router whose routes are keyed by `K`.
+    pub fn keyed() -> Self {
+        Self::default()
+    }
+    /// Like `keyed`, counting matches as `with_stats` does.
+    pub fn keyed_with_stats() -> Self {
+        Self {
+            counters: Counters {
+                enabled: true,
+                ..Counters::default()
+            },
+            ..Self::default()
+        }
+    }
+    /// `None` unless the router was built with `with_stats` or `keyed_with_stats`.
+    pub fn stats(&self) -> Option<RouterStats> {
+        self.counters.stats(&self.routes)
+    }
+    pub fn with_duplicates(mut self, policy: DuplicatePolicy) -> Self {
+        self.duplicates = policy;
+        self
+    }
+    /// Makes adding a route an error (`RouterError::ShadowedRoute`) when
+    /// it could never match because a route tried before it matches every
+    /// path it does, or when it would do that to an existing route. See
+    /// `Pattern::covers` for what's detected; lazy routes and mounts
+    /// aren't checked.
+    pub fn reject_shadowed(mut self) -> Self {
+        self.reject_shadowed = true;
+        self
+    }
+    /// `add` for a keyed router. Routes are only duplicates if their keys
+    /// are equal too.
+    ///
+    /// # Panics
+    ///
+    /// As `add` does; use `try_add_keyed` to handle those cases instead.
+    pub fn add_keyed(&mut self, key: K, pattern: Pattern, value: T) -> RouteId {
+        self.try_add_keyed(key, pattern, value)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+    /// `try_add` for a keyed router.
+    pub fn try_add_keyed(
+        &mut self,
+        key: K,
+        pattern: Pattern,
+        value: T,
+    ) -> Result<RouteId, RouterError> {
+        self.try_insert(key, None, pattern, value, false)
+    }
+    /// `add_host` for a keyed router.
+    ///
+    /// # Panics
+    ///
+    /// As `add_host` does; use `try_add_host_keyed` to handle those cases
+    /// instead.
+    pub fn add_host_keyed(&mut self, key: K, host: Pattern, pattern: Pattern, value: T) -> RouteId {
+        self.try_add_host_keyed(key, host, pattern, value)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+    /// `try_add_host` for a keyed router.
+    pub fn try_add_host_keyed(
+        &mut self,
+        key: K,
+        host: Pattern,
+        pattern: Pattern,
+        value: T,
+    ) -> Result<RouteId, RouterError> {
+        self.try_insert(key, Some(host), pattern, value, false)
+    }
+    /// `mount` for a keyed router.
+    ///
+    /// # Panics
+    ///
+    /// As `mount` does; use `try_mount_keyed` to handle those cases
+    /// instead.
+    pub fn mount_keyed(&mut self, key: K, prefix: Pattern, value: T) -> RouteId {
+        self.try_mount_keyed(key, prefix, value)
+            .unwrap_or_else(|err| panic!("{err}"))
+    }
+    /// `try_mount` for a keyed router.
+    pub fn try_mount_keyed(
+        &mut self,
+        key: K,
+        prefix: Pattern,
+        value: T,
+    ) -> Result<RouteId, RouterError> {
+        let mut segments = prefix.segments().to_vec();
+        if segments.last() == Some(&Segment::Terminus) {
+            segments.pop();
+        }
+        if !prefix.query().is_empty()
+            || segments.iter().any(|segment| {
+                matches!(segment, Segment::Repeat(_) | Segment::CatchAll(..))
+                    || segment.is_optional()
+            })
+        {
+            return Err(RouterError::InvalidMount {
+                pattern: prefix.to_string(),
+            });
+        }
+        segments.push(Segment::CatchAll(None, RestChecks::default()));
+        self.try_insert(key, None, Pattern::new(segments), value, true)
+    }
+    fn try_insert(
+        &mut self,
+        key: K,
+        host: Option<Pattern>,
+        pattern: Pattern,
+        value: T,
+        mount: bool,
+    ) -> Result<RouteId, RouterError> {
+        if self.duplicates != DuplicatePolicy::Allow {
+            let existing = self.routes.iter_mut().find(|route| match &route.pattern {
+                RoutePattern::Parsed(p) => {
+                    route.key == key
+                        && route.mount == mount
+                        && p.structurally_eq(&pattern)
+                        && match (&route.host, &host) {
+                            (Some(a), Some(b)) => a.structurally_eq(b),
+                            (a, b) => a.is_none() && b.is_none(),
+                        }
+                }
+                RoutePattern::Lazy { .. } => false,
+            });
+            if let Some(route) = existing {
+                if self.duplicates == DuplicatePolicy::Error {
+                    return Err(RouterError::DuplicatePattern {
+                        pattern: pattern.to_string(),
+                    });
+                }
+                route.pattern = RoutePattern::Parsed(pattern);
+                route.value = value;
+                return Ok(route.id);
+            }
+        }
+        let specificity = pattern.specificity();
+        if self.reject_shadowed && !mount {
+            self.check_shadowing(&key, &host, &pattern, &specificity)?;
+        }
+        Ok(self.insert(
+            key,
+            host,
+            RoutePattern::Parsed(pattern),
+            specificity,
+            value,
+            mount,
+        ))
+    }
+    fn check_shadowing(
+        &self,
+        key: &K,
+        host: &Option<Pattern>,
+        pattern: &Pattern,
+        specificity: &[u8],
+    ) -> Result<(), RouterError> {
+        let index = self.position(false, specificity, host);
+        for (i, route) in self.routes.iter().enumerate() {
+            let RoutePattern::Parsed(existing) = &route.pattern else {
+                continue;
+            };
+            if route.key != *key || route.mount {
+                continue;
+            }
+            let (first, first_host, second, second_host) = if i < index {
+                (existing, &route.host, pattern, host)
+            } else {
+                (pattern, host, existing, &route.host)
+            };
+            let host_covered = match (first_host, second_host) {
+                (None, _) => true,
+                (Some(a), Some(b)) => a.covers(b),
+                (Some(_), None) => false,
+            };
+            if host_covered && first.covers(second) {
+                return Err(RouterError::ShadowedRoute {
+                    pattern: second.to_string(),
+                    by: first.to_string(),
+                });
+            }
+        }
+        Ok(())
+    }
+    /// `add_lazy` for a keyed router.
+    pub fn add_lazy_keyed(&mut self, key: K, source: impl Into<String>, value: T) -> RouteId {
+        let source = source.into();
+        let specificity = source_specificity(&source);
+        let pattern = RoutePattern::Lazy {
+            source,
+            parsed: OnceLock::new(),
+        };
+        self.insert(key, None, pattern, specificity, value, false)
+    }
+    fn insert(
+        &mut self,
+        key: K,
+        host: Option<Pattern>,
+        pattern: RoutePattern,
+        specificity: Vec<u8>,
+        value: T,
+        mount: bool,
+    ) -> RouteId {
+        let index = self.position(mount, &specificity, &host);
+        let id = RouteId(self.next_id);
+        self.next_id += 1;
+        self.routes.insert(
+            index,
+            Route {
+                id,
+                pattern,
+                key,
+                mount,
+                host,
+                specificity,
+                value,
+                hits: AtomicU64::new(0),
+            },
+        );
+        id
+    }
+    /// The value of the route added as `id`.
+    pub fn get(&self, id: RouteId) -> Option<&T> {
+        get(&self.routes, id)
+    }
+    /// Where a new route goes in match-priority order.
+    fn position(&self, mount: bool, specificity: &[u8], host: &Option<Pattern>) -> usize {
+        // mounts go after every other route, by specificity among
+        // themselves, and host routes before hostless ones that are as
+        // specific
+        fn rank<'a>(
+            mount: bool,
+            specificity: &'a [u8],
+            host: &Option<Pattern>,
+        ) -> (bool, std::cmp::Reverse<&'a [u8]>, bool) {
+            (mount, std::cmp::Reverse(specificity), host.is_none())
+        }
+        let new = rank(mount, specificity, host);
+        self.routes
+            .partition_point(|route| rank(route.mount, &route.specificity, &route.host) <= new)
+    }
+    pub fn len(&self) -> usize {
+        self.routes.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+    /// Matches `path` against only the routes added under `key`.
+    pub fn match_keyed(&self, key: &K, path: &str) -> Option<RouteMatch<'_, T>> {
+        self.find(key, None, path).map(|(_, matched)| matched)
+    }
+    /// `match_host_path` for a keyed router.
+    pub fn match_host_keyed(&self, key: &K, host: &str, path: &str) -> Option<RouteMatch<'_, T>> {
+        self.find(key, Some(host), path).map(|(_, matched)| matched)
+    }
+    /// `match_owned` for a keyed router.
+    pub fn match_owned_keyed(&self, key: &K, path: &str) -> Option<OwnedMatch<T>>
+    where
+        T: Clone,
+    {
+        self.find(key, None, path)
+            .map(|(index, matched)| matched.into_owned(index))
+    }
+    fn find(&self, key: &K, host: Option<&str>, path: &str) -> Option<(usize, RouteMatch<'_, T>)> {
+        let found = self
+            .routes
+            .iter()
+            .enumerate()
+            .filter(|(_, route)| route.key == *key)
+            .find_map(|(index, route)| Some((index, route.match_path(host, path)?)));
+        self.counters
+            .record(found.as_ref().map(|(index, _)| &self.routes[*index]));
+        found
+    }
+    /// A table of every route in match-priority order, for debugging.
+    /// Each row has the route's rank, its pattern (after its host, for a
+    /// host route), its vars as `name:type`, its specificity (one digit a
+    /// segment, higher winning) and, when counting, its hits. Lazy routes
+    /// that haven't been parsed yet list no vars.
+    pub fn dump(&self) -> String {
+        dump(&self.routes, &self.counters)
+    }
+    pub fn compile(self) -> CompiledRouter<T, K> {
+        CompiledRouter::from(self)
+    }
+}
+
+impl<T: Clone> RouteMatch<'_, T> {
+    fn into_owned(self, index: usize) -> OwnedMatch<T> {
+        OwnedMatch {
+            index,
+            id: self.id,
+            value: self.value.clone(),
+            captures: self.captures,
+            remainder: self.remainder,
+        }
+    }
+}
+
+fn dump<T, K>(routes: &[Route<T, K>], counters: &Counters) -> String {
+    let mut rows = vec![["rank", "pattern", "vars", "specificity", "hits"].map(String::from)];
+    for (rank, route) in routes.iter().enumerate() {
+        let mut vars = Vec::new();
+        let pattern = match &route.pattern {
+            RoutePattern::Parsed(pattern) => Some(pattern),
+            RoutePattern::Lazy { parsed, .. } => parsed.get().and_then(Option::as_ref),
+        };
+        if let Some(pattern) = pattern {
+            collect_vars(pattern, &mut vars);
+        }
+        let source = match &route.host {
+            Some(host) => {
+                collect_vars(host, &mut vars);
+                let host = host.to_string();
+                format!("{}{}", host[1..].replace('/', "."), route.source())
+            }
+            None => route.source(),
+        };
+        let specificity = route.specificity.iter().map(u8::to_string).collect();
+        let hits = route.hits.load(Ordering::Relaxed).to_string();
+        rows.push([rank.to_string(), source, vars.join(" "), specificity, hits]);
+    }
+    let columns = if counters.enabled { 5 } else { 4 };
+    let widths: Vec<usize> = (0..columns)
+        .map(|i| {
+            rows.iter()
+                .map(|row| row[i].chars().count())
+                .max()
+                .unwrap_or(0)
+        })
+        .collect();
+    let mut table = String::new();
+    for row in &rows {
+        let line: Vec<String> = row[..columns]
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect();
+        table.push_str(line.join("  ").trim_end());
+        table.push('\n');
+    }
+    table
+}
+
+fn collect_vars(pattern: &Pattern, vars: &mut Vec<String>) {
+    fn walk(segments: &[Segment], vars: &mut Vec<String>) {
+        for segment in segments {
+            match segment {
+                Segment::Var(var) => vars.push(format!("{}:{}", var.name, var.seg_type().as_str())),
+                Segment::Repeat(group) | Segment::Optional(group) => walk(group, vars),
+                Segment::CatchAll(Some(name), _) => vars.push(format!("{name}:**")),
+                _ => {}
+            }
+        }
+    }
+    walk(pattern.segments(), vars);
+    for param in pattern.query() {
+        walk(std::slice::from_ref(&param.value), vars);
+    }
+}
+
+fn get<T, K>(routes: &[Route<T, K>], id: RouteId) -> Option<&T> {
+    routes
+        .iter()
+        .find(|route| route.id == id)
+        .map(|route| &route.value)
+}
+
+impl<T, K> Route<T, K> {
+    fn pattern(&self) -> Option<&Pattern> {
+        match &self.pattern {
+            RoutePattern::Parsed(pattern) => Some(pattern),
+            RoutePattern::Lazy { source, parsed } => {
+                parsed.get_or_init(|| source.parse().ok()).as_ref()
+            }
+        }
+    }
+    /// The pattern as written, without forcing a lazy parse.
+    fn source(&self) -> String {
+        match &self.pattern {
+            RoutePattern::Parsed(pattern) => pattern.to_string(),
+            RoutePattern::Lazy { source, .. } => source.clone(),
+        }
+    }
+    /// Whether the route can match requests to `host`, `None` being a
+    /// match on the path alone.
+    fn accepts_host(&self, host: Option<&str>) -> bool {
+        match (&self.host, host) {
+            (None, _) => true,
+            (Some(pattern), Some(host)) => pattern.match_host(host).is_ok(),
+            (Some(_), None) => false,
+        }
+    }
+    fn match_path(&self, host: Option<&str>, path: &str) -> Option<RouteMatch<'_, T>> {
+        let pattern = self.pattern()?;
+        if !pattern.may_match(path) {
+            return None;
+        }
+        let host_captures = match (&self.host, host) {
+            (None, _) => Vec::new(),
+            (Some(pattern), Some(host)) => pattern.match_host(host).ok()?,
+            (Some(_), None) => return None,
+        };
+        let mut captures = pattern.match_path(path).ok()?;
+        // a mount's last capture is the catch-all `try_mount_keyed` appended
+        let remainder = match captures.last() {
+            Some(MatchResult::Matched {
+                value: MatchValue::String(rest),
+                ..
+            }) if self.mount => Some(format!("/{rest}")),
+            _ => None,
+        };
+        captures.extend(host_captures);
+        Some(RouteMatch {
+            id: self.id,
+            pattern,
+            value: &self.value,
+            captures,
+            remainder,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    statics: HashMap<String, Node>,
+    terminus: Option<Box<Node>>,
+    vars: Vec<(Var, Node)>,
+    /// Indices of routes whose pattern ends at this node.
+    routes: Vec<usize>,
+}
+
+impl Node {
+    fn new() -> Self {
+        Self {
+            statics: HashMap::new(),
+            terminus: None,
+            vars: Vec::new(),
+            routes: Vec::new(),
+        }
+    }
+    fn insert(&mut self, segments: &[Segment], index: usize) {
+        let Some((first, rest)) = segments.split_first() else {
+            self.routes.push(index);
+            return;
+        };
+        let child = match first {
+            Segment::Repeat(_)
+            | Segment::Optional(_)
+            | Segment::CatchAll(..)
+            | Segment::Glob(_) => {
+                unreachable!("dynamic routes are not indexed")
+            }
+            Segment::Static(s) => self.statics.entry(s.clone()).or_insert_with(Node::new),
+            Segment::Terminus => self.terminus.get_or_insert_with(|| Box::new(Node::new())),
+            Segment::Var(v) => {
+                let position = match self.vars.iter().position(|(var, _)| var == v) {
+                    Some(position) => position,
+                    None => {
+                        self.vars.push((v.clone(), Node::new()));
+                        self.vars.len() - 1
+                    }
+                };
+                &mut self.vars[position].1
+            }
+        };
+        child.insert(rest, index);
+    }
+    /// Returns the lowest-indexed (i.e. highest-priority) route matching
+    /// the remaining parts. Any match through a static or terminus child
+    /// outranks every match through a var child, so those are tried first
+    /// and returned without looking further. Only routes that `accept`
+    /// are considered.
+    fn find(&self, parts: &[&str], accept: &impl Fn(usize) -> bool) -> Option<usize> {
+        let first_accepted = |node: &Node| node.routes.iter().copied().find(|&i| accept(i));
+        let Some((first, rest)) = parts.split_first() else {
+            return first_accepted(self)
+                .or_else(|| self.terminus.as_deref().and_then(first_accepted));
+        };
+        if let Some(found) = self
+            .statics
+            .get(*first)
+            .and_then(|node| node.find(rest, accept))
+        {
+            return Some(found);
+        }
+        if first.is_empty() {
+            if let Some(found) = self
+                .terminus
+                .as_ref()
+                .and_then(|node| node.find(rest, accept))
+            {
+                return Some(found);
+            }
+        }
+        self.vars
+            .iter()
+            .filter(|(var, _)| var.try_match(first).is_ok())
+            .filter_map(|(_, node)| node.find(rest, accept))
+            .min()
+    }
+}
+
+/// A `Router` compiled into a segment-level prefix tree, so matching walks
+/// the path once instead of trying every route. Produces the same results
+/// as the `Router` it was built from.
+#[derive(Debug, Clone)]
+pub struct CompiledRouter<T, K = ()> {
+    routes: Vec<Route<T, K>>,
+    counters: Counters,
+    root: Node,
+    /// Routes whose patterns consume a variable number of path segments
+    /// and so can't live in the tree; these are tried linearly.
+    dynamic: Vec<usize>,
+}
+
+impl<T, K> From<Router<T, K>> for CompiledRouter<T, K> {
+    fn from(router: Router<T, K>) -> Self {
+        let mut root = Node::new();
+        let mut dynamic = Vec::new();
+        for (index, route) in router.routes.iter().enumerate() {
+            match route.pattern() {
+                Some(pattern) if pattern.is_dynamic() => dynamic.push(index),
+                Some(pattern) => root.insert(pattern.segments(), index),
+                None => {}
+            }
+        }
+        Self {
+            routes: router.routes,
+            counters: router.counters,
+            root,
+            dynamic,
+        }
+    }
+}
+
+impl<T> CompiledRouter<T> {
+    pub fn match_path(&self, path: &str) -> Option<RouteMatch<'_, T>> {
+        self.match_keyed(&(), path)
+    }
+    /// See `Router::match_owned`.
+    pub fn match_owned(&self, path: &str) -> Option<OwnedMatch<T>>
+    where
+        T: Clone,
+    {
+        self.match_owned_keyed(&(), path)
+    }
+    /// See `Router::match_host_path`.
+    pub fn match_host_path(&self, host: &str, path: &str) -> Option<RouteMatch<'_, T>> {
+        self.match_host_keyed(&(), host, path)
+    }
+}
+
+impl<T, K: PartialEq> CompiledRouter<T, K> {
+    /// See `Router::get`.
+    pub fn get(&self, id: RouteId) -> Option<&T> {
+        get(&self.routes, id)
+    }
+    pub fn len(&self) -> usize {
+        self.routes.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+    /// `None` unless the source router was built with `Router::with_stats`.
+    pub fn stats(&self) -> Option<RouterStats> {
+        self.counters.stats(&self.routes)
+    }
+    /// See `Router::dump`.
+    pub fn dump(&self) -> String {
+        dump(&self.routes, &self.counters)
+    }
+    /// See `Router::match_keyed`.
+    pub fn match_keyed(&self, key: &K, path: &str) -> Option<RouteMatch<'_, T>> {
+        self.find(key, None, path).map(|(_, matched)| matched)
+    }
+    /// See `Router::match_host_keyed`.
+    pub fn match_host_keyed(&self, key: &K, host: &str, path: &str) -> Option<RouteMatch<'_, T>> {
+        self.find(key, Some(host), path).map(|(_, matched)| matched)
+    }
+    /// See `Router::match_owned_keyed`.
+    pub fn match_owned_keyed(&self, key: &K, path: &str) -> Option<OwnedMatch<T>>
+    where
+        T: Clone,
+    {
+        self.find(key, None, path)
+            .map(|(index, matched)| matched.into_owned(index))
+    }
+    fn find(&self, key: &K, host: Option<&str>, path: &str) -> Option<(usize, RouteMatch<'_, T>)> {
+        let accept = |index: usize| {
+            let route = &self.routes[index];
+            route.key == *key && route.accepts_host(host)
+        };
+        let parts: Vec<&str> = split_path(path).collect();
+        let indexed = self.root.find(&parts, &accept);
+        let found = self
+            .dynamic
+            .iter()
+            .copied()
+            .filter(|&index| accept(index))
+            .take_while(|&index| indexed.is_none_or(|found| index < found))
+            .chain(indexed)
+            .find_map(|index| Some((index, self.routes[index].match_path(host, path)?)));
+        self.counters
+            .record(found.as_ref().map(|(index, _)| &self.routes[*index]));
+        found
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::segments::MatchValue;
+
+    fn router() -> Router<&'static str> {
+        let mut router = Router::new();
+        for (pattern, value) in [
+            ("/users/:id<number>", "user"),
+            ("/users/:name", "user_by_name"),
+            ("/users/new", "new_user"),
+            ("/users/:id<number>/posts/", "posts"),
+            ("/users/:id<number>/posts/:day<date>", "posts_on"),
+            ("/:section/about", "about"),
+            ("/", "root"),
+            ("/users/:id<number>/(:k/:v)*", "user_filters"),
+            ("/api/(v:version<number>/)?users", "api_users"),
+        ] {
+            router.add(pattern.parse().unwrap(), value);
+        }
+        router
+    }
+
+    #[test]
+    fn router_static_beats_var() {
+        let router = router();
+        assert_eq!(router.match_path("/users/new").unwrap().value, &"new_user");
+        assert_eq!(router.match_path("/users/5").unwrap().value, &"user");
+        assert_eq!(
+            router.match_path("/users/bob").unwrap().value,
+            &"user_by_name"
+        );
+    }
+    #[test]
+    fn router_match_captures() {
+        let router = router();
+        let matched = router.match_path("/users/5/posts/2021-01-01").unwrap();
+        assert_eq!(matched.value, &"posts_on");
+        assert_eq!(
+            matched.captures[3],
+            MatchResult::Matched {
+                value: MatchValue::Date(jiff::civil::date(2021, 1, 1)),
+                name: Some("day".to_string()),
+                segment_index: Some(3),
+                span: Some((15, 25))
+            }
+        );
+    }
+    #[test]
+    fn router_no_match() {
+        assert!(router().match_path("/nowhere/at/all").is_none());
+    }
+    #[test]
+    fn router_stats_count_hits() {
+        let mut router = Router::with_stats();
+        router.add("/users/:id<number>".parse().unwrap(), "user");
+        router.add("/posts".parse().unwrap(), "posts");
+        router.match_path("/users/1");
+        router.match_path("/users/2");
+        router.match_path("/nowhere");
+        let stats = router.stats().unwrap();
+        assert_eq!(stats.attempts, 3);
+        let user = stats
+            .routes
+            .iter()
+            .find(|r| r.pattern == "/users/:id<number>")
+            .unwrap();
+        assert_eq!(user.hits, 2);
+        assert!(Router::<()>::new().stats().is_none());
+    }
+    #[test]
+    fn compiled_router_keeps_stats() {
+        let mut router = Router::with_stats();
+        router.add("/posts".parse().unwrap(), "posts");
+        let compiled = router.compile();
+        compiled.match_path("/posts");
+        assert_eq!(compiled.stats().unwrap().routes[0].hits, 1);
+    }
+    #[test]
+    fn compiled_router_matches_linear_router() {
+        let linear = router();
+        let compiled = router().compile();
+        for path in [
+            "/",
+            "",
+            "/users/new",
+            "/users/5",
+            "/users/bob",
+            "/users/5/posts",
+            "/users/5/posts/",
+            "/users/5/posts/2021-01-01",
+            "/users/bob/posts/2021-01-01",
+            "/users/5/color/red",
+            "/users/5/color",
+            "/users/about",
+            "/news/about",
+            "/nowhere/at/all",
+            "/api/users",
+            "/api/v2/users",
+        ] {
+            assert_eq!(linear.match_path(path), compiled.match_path(path), "{path}");
+        }
+    }
+    #[test]
+    fn router_excluded_words_fall_through() {
+        let mut router = Router::new();
+        router.add(
+            "/posts/:slug<string(not=new,edit)>".parse().unwrap(),
+            "post",
+        );
+        router.add("/posts/new".parse().unwrap(), "new_post");
+        router.add("/:any/edit".parse().unwrap(), "edit");
+        assert_eq!(router.match_path("/posts/new").unwrap().value, &"new_post");
+        assert_eq!(router.match_path("/posts/hello").unwrap().value, &"post");
+        assert_eq!(router.match_path("/posts/edit").unwrap().value, &"edit");
+    }
+    #[test]
+    fn router_duplicate_policies() {
+        let mut router = Router::new();
+        let id = router.add("/users/:id<number>".parse().unwrap(), 1);
+        assert_eq!(router.add("/users/:uid<number>".parse().unwrap(), 2), id);
+        assert_eq!(router.len(), 1);
+        assert_eq!(router.get(id), Some(&2));
+        let matched = router.match_path("/users/5").unwrap();
+        assert_eq!(matched.value, &2);
+        assert_eq!(matched.pattern.to_string(), "/users/:uid<number>");
+
+        let mut router = Router::new().with_duplicates(DuplicatePolicy::Error);
+        router.add("/users".parse().unwrap(), 1);
+        assert_eq!(
+            router.try_add("/users".parse().unwrap(), 2),
+            Err(RouterError::DuplicatePattern {
+                pattern: "/users".to_string()
+            })
+        );
+        assert_eq!(router.len(), 1);
+
+        let mut router = Router::new().with_duplicates(DuplicatePolicy::Allow);
+        router.add("/users".parse().unwrap(), 1);
+        router.add("/users".parse().unwrap(), 2);
+        assert_eq!(router.len(), 2);
+        assert_eq!(router.match_path("/users").unwrap().value, &1);
+    }
+    #[test]
+    fn router_route_ids() {
+        let mut router = Router::new();
+        let user = router.add("/users/:id<number>".parse().unwrap(), "user");
+        let new_user = router.add("/users/new".parse().unwrap(), "new_user");
+        assert_eq!((user, new_user), (RouteId(0), RouteId(1)));
+        let matched = router.match_path("/users/new").unwrap();
+        assert_eq!(matched.id, new_user);
+        assert_eq!(router.get(matched.id), Some(&"new_user"));
+        let compiled = router.compile();
+        let matched = compiled.match_owned("/users/5").unwrap();
+        assert_eq!((matched.index, matched.id), (1, user));
+        assert_eq!(compiled.get(matched.id), Some(&"user"));
+        assert_eq!(compiled.get(RouteId(2)), None);
+    }
+    #[test]
+    fn router_dump() {
+        let mut router = Router::with_stats();
+        router.add("/users/:id<integer>/posts/:day<date>".parse().unwrap(), 0);
+        router.add("/".parse().unwrap(), 1);
+        router.add_host(
+            Pattern::parse_host(":tenant.example.com").unwrap(),
+            "/search?q=:q".parse().unwrap(),
+            2,
+        );
+        router.match_path("/users/5/posts/2021-01-01");
+        let dump = router.dump();
+        for route in ["/users/:id<integer>/posts/:day<date>", "/", "/search?q=:q"] {
+            assert!(dump.contains(route), "{route}");
+        }
+        assert_eq!(
+            dump,
+            "\
+rank  pattern                                          vars                    specificity  hits
+0     :tenant<string>.example.com/search?q=:q<string>  q:string tenant:string  23           0
+1     /                                                                        23           0
+2     /users/:id<integer>/posts/:day<date>             id:integer day:date     21213        1
+"
+        );
+    }
+    #[test]
+    fn router_rejects_shadowed_routes() {
+        let mut router = Router::new().reject_shadowed();
+        router.add("/users/:id<number>".parse().unwrap(), "user");
+        // tried first, so not shadowed
+        router.add("/users/new".parse().unwrap(), "new_user");
+        assert_eq!(
+            router.try_add("/users/:n<integer>".parse().unwrap(), "by_integer"),
+            Err(RouterError::ShadowedRoute {
+                pattern: "/users/:n<integer>".to_string(),
+                by: "/users/:id<number>".to_string()
+            })
+        );
+        // host routes are tried first, and a hostless route only shadows
+        // ones without a host
+        router.add("/posts/:slug".parse().unwrap(), "post");
+        let api = Pattern::parse_host("api.example.com").unwrap();
+        assert!(router
+            .try_add_host(api, "/posts/:n<integer>".parse().unwrap(), "api_post")
+            .is_ok());
+        assert_eq!(
+            router.try_add("/posts/:n<integer(1..10)>".parse().unwrap(), "late"),
+            Err(RouterError::ShadowedRoute {
+                pattern: "/posts/:n<integer(1..=9)>".to_string(),
+                by: "/posts/:slug<string>".to_string()
+            })
+        );
+        assert_eq!(router.len(), 4);
+        // without the check, both are kept
+        let mut router = Router::new();
+        router.add("/users/:id<number>".parse().unwrap(), "user");
+        router.add("/users/:n<integer>".parse().unwrap(), "by_integer");
+        assert_eq!(router.len(), 2);
+    }
+    #[test]
+    #[should_panic(expected = "can never match")]
+    fn router_add_panics_on_shadowed_route() {
+        let mut router = Router::new().reject_shadowed();
+        router.add("/users/:id<number>".parse().unwrap(), "user");
+        router.add("/users/:n<integer>".parse().unwrap(), "by_integer");
+    }
+    #[test]
+    fn router_lazy_routes() {
+        let mut router = Router::new();
+        router.add_lazy("/users/:id<number>", "user");
+        router.add_lazy("/users/new", "new_user");
+        router.add_lazy("/broken/:x<nope>", "broken");
+        assert!(router.routes.iter().all(|route| match &route.pattern {
+            RoutePattern::Lazy { parsed, .. } => parsed.get().is_none(),
+            RoutePattern::Parsed(_) => false,
+        }));
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                scope.spawn(|| {
+                    assert_eq!(router.match_path("/users/5").unwrap().value, &"user");
+                });
+            }
+        });
+        for _ in 0..2 {
+            assert_eq!(router.match_path("/users/5").unwrap().value, &"user");
+            assert_eq!(router.match_path("/users/new").unwrap().value, &"new_user");
+        }
+        assert!(router.match_path("/broken/x").is_none());
+        let compiled = router.compile();
+        assert_eq!(compiled.match_path("/users/7").unwrap().value, &"user");
+    }
+    #[test]
+    fn router_match_owned_outlives_router() {
+        let owned = {
+            let router = router();
+            router.match_owned("/users/5/posts/2021-01-01").unwrap()
+        };
+        assert_eq!(owned.value, "posts_on");
+        assert_eq!(
+            owned.captures[1],
+            MatchResult::Matched {
+                value: MatchValue::Number(5.0),
+                name: Some("id".to_string()),
+                segment_index: Some(1),
+                span: Some((7, 8))
+            }
+        );
+        let router = router();
+        let compiled = router.clone().compile();
+        assert_eq!(
+            compiled.match_owned("/users/new"),
+            router.match_owned("/users/new")
+        );
+    }
+    #[test]
+    fn compiled_router_tie_goes_to_first_added() {
+        let mut router = Router::new();
+        router.add("/:a<number>/x/:c".parse().unwrap(), 0);
+        router.add("/:b<string>/x/y".parse().unwrap(), 1);
+        router.add("/:a<number>/x/y".parse().unwrap(), 2);
+        let linear_value = *router.match_path("/5/x/y").unwrap().value;
+        let compiled = router.compile();
+        assert_eq!(*compiled.match_path("/5/x/y").unwrap().value, linear_value);
+    }
+    #[test]
+    fn router_keyed_by_method() {
+        let mut router = Router::keyed();
+        router.add_keyed("GET", "/users/:id<number>".parse().unwrap(), "show");
+        router.add_keyed("POST", "/users".parse().unwrap(), "create");
+        router.add_keyed("GET", "/users".parse().unwrap(), "index");
+        assert_eq!(
+            router.match_keyed(&"GET", "/users/5").unwrap().value,
+            &"show"
+        );
+        assert_eq!(
+            router.match_keyed(&"POST", "/users").unwrap().value,
+            &"create"
+        );
+        assert_eq!(
+            router.match_keyed(&"GET", "/users").unwrap().value,
+            &"index"
+        );
+        assert!(router.match_keyed(&"POST", "/users/5").is_none());
+        assert!(router.match_keyed(&"DELETE", "/users").is_none());
+        let compiled = router.compile();
+        assert_eq!(
+            compiled.match_keyed(&"GET", "/users/5").unwrap().value,
+            &"show"
+        );
+        assert_eq!(
+            compiled.match_keyed(&"POST", "/users").unwrap().value,
+            &"create"
+        );
+        assert!(compiled.match_keyed(&"POST", "/users/5").is_none());
+    }
+    #[test]
+    fn router_mounts_prefer_deepest_prefix() {
+        let mut router = Router::new();
+        router.mount("/static".parse().unwrap(), "static");
+        router.mount("/static/css/".parse().unwrap(), "css");
+        router.add("/static/:file".parse().unwrap(), "file");
+        let matched = router.match_path("/static/css/site/main.css").unwrap();
+        assert_eq!(matched.value, &"css");
+        assert_eq!(
+            matched.captures.last(),
+            Some(&MatchResult::Matched {
+                value: MatchValue::String("site/main.css".to_string()),
+                name: None,
+                segment_index: Some(2),
+                span: Some((12, 25))
+            })
+        );
+        assert_eq!(
+            router.match_path("/static/js/a.js").unwrap().value,
+            &"static"
+        );
+        assert_eq!(router.match_path("/static/a.js").unwrap().value, &"file");
+        assert!(router.match_path("/other/a.js").is_none());
+        assert_eq!(router.match_path("/static/a.js").unwrap().remainder, None);
+        assert!(router
+            .try_mount("/files/(:a)*".parse().unwrap(), "bad")
+            .is_err());
+        let compiled = router.compile();
+        for (path, value) in [
+            ("/static/css/site/main.css", "css"),
+            ("/static/js/a.js", "static"),
+            ("/static/a.js", "file"),
+        ] {
+            assert_eq!(compiled.match_path(path).unwrap().value, &value, "{path}");
+        }
+    }
+    #[test]
+    fn router_matches_host_and_path() {
+        let mut router = Router::new();
+        router.add_host(
+            Pattern::parse_host("api.*.example.com").unwrap(),
+            "/users/:id<number>".parse().unwrap(),
+            "api",
+        );
+        router.add_host(
+            Pattern::parse_host(":tenant.example.com").unwrap(),
+            "/users/:id<number>".parse().unwrap(),
+            "tenant",
+        );
+        router.add("/users/:id<number>".parse().unwrap(), "any");
+        let matched = router
+            .match_host_path("API.eu.example.com", "/users/5")
+            .unwrap();
+        assert_eq!(matched.value, &"api");
+        let named: Vec<_> = matched
+            .captures
+            .iter()
+            .filter_map(|capture| match capture {
+                MatchResult::Matched {
+                    value,
+                    name: Some(name),
+                    ..
+                } => Some((name.as_str(), value)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(named, [("id", &MatchValue::Number(5.0))]);
+        // the wildcard label is captured unnamed, after the path's captures
+        assert_eq!(
+            matched.captures[2],
+            MatchResult::Matched {
+                value: MatchValue::String("api".to_string()),
+                name: None,
+                segment_index: Some(0),
+                span: Some((0, 3))
+            }
+        );
+        let tenant = router
+            .match_host_path("acme.example.com", "/users/5")
+            .unwrap();
+        assert_eq!(tenant.value, &"tenant");
+        assert_eq!(
+            tenant.captures[2],
+            MatchResult::Matched {
+                value: MatchValue::String("acme".to_string()),
+                name: Some("tenant".to_string()),
+                segment_index: Some(0),
+                span: Some((0, 4))
+            }
+        );
+        assert_eq!(
+            router
+                .match_host_path("example.org", "/users/5")
+                .unwrap()
+                .value,
+            &"any"
+        );
+        assert_eq!(router.match_path("/users/5").unwrap().value, &"any");
+        let compiled = router.compile();
+        assert_eq!(
+            compiled
+                .match_host_path("api.us.example.com", "/users/5")
+                .unwrap()
+                .value,
+            &"api"
+        );
+        assert_eq!(compiled.match_path("/users/5").unwrap().value, &"any");
+    }
+    #[test]
+    fn router_mount_returns_remainder() {
+        let mut router = Router::new();
+        router.mount("/admin".parse().unwrap(), "admin");
+        let matched = router.match_path("/admin/users/5").unwrap();
+        assert_eq!(matched.remainder.as_deref(), Some("/users/5"));
+        let mut admin = Router::new();
+        admin.add("/users/:id<integer>".parse().unwrap(), "user");
+        let inner = admin.match_path(&matched.remainder.unwrap()).unwrap();
+        assert_eq!(inner.value, &"user");
+        assert_eq!(
+            router.match_owned("/admin").unwrap().remainder.as_deref(),
+            Some("/")
+        );
+    }
+}
@@ -0,0 +1,32 @@
+//! Rust enums as enum vars. `#[derive(SegEnum)]` (with the `derive`
+//! feature) implements `SegEnum` for an enum of unit variants, matching
+//! each variant by its name. `#[seg_enum(rename = "...")]` on a variant
+//! overrides its name, and `#[seg_enum(rename_all = "...")]` on the enum
+//! renames them all (`"lowercase"`, `"UPPERCASE"`, `"snake_case"` or
+//! `"kebab-case"`).
+//!
+//! A match captures the variant's name as a `MatchValue::String`, like any
+//! enum var; `SegEnum::from_value` turns it back into the variant.
+
+use crate::segments::{MatchValue, Var};
+
+pub trait SegEnum: Sized {
+    /// The name each variant matches, in declaration order.
+    const NAMES: &'static [&'static str];
+    /// The variant named `name`, compared exactly.
+    fn from_name(name: &str) -> Option<Self>;
+    /// An enum var named `name` matching `NAMES`.
+    fn var(name: impl Into<String>) -> Var {
+        Var::one_of(
+            name.into(),
+            Self::NAMES.iter().map(|name| name.to_string()).collect(),
+        )
+    }
+    /// The variant a var from `SegEnum::var` captured.
+    fn from_value(value: &MatchValue) -> Option<Self> {
+        Self::from_name(value.as_str()?)
+    }
+}
+
+#[cfg(feature = "derive")]
+pub use urlpather_derive::SegEnum;
@@ -0,0 +1,85 @@
+use std::sync::{Arc, RwLock};
+
+use crate::router::CompiledRouter;
+
+/// A `CompiledRouter` shared across threads, whose route table can be
+/// rebuilt and swapped in while other threads keep matching.
+///
+/// `CompiledRouter` is `Send + Sync` and matches through `&self`, so the
+/// recommended pattern is to `load` the current router once per request and
+/// match against that `Arc`: no lock is held while matching, and a `store`
+/// from another thread only affects requests that call `load` afterwards.
+/// This isn't lock-free: `load` takes a short read lock to clone the `Arc`
+/// and `store` a write lock to replace it, so a load can briefly wait on a
+/// concurrent store.
+#[derive(Debug)]
+pub struct SharedRouter<T, K = ()> {
+    current: RwLock<Arc<CompiledRouter<T, K>>>,
+}
+
+impl<T, K> SharedRouter<T, K> {
+    pub fn new(router: CompiledRouter<T, K>) -> Self {
+        Self {
+            current: RwLock::new(Arc::new(router)),
+        }
+    }
+    /// The router in effect right now.
+    pub fn load(&self) -> Arc<CompiledRouter<T, K>> {
+        Arc::clone(&self.current.read().unwrap_or_else(|e| e.into_inner()))
+    }
+    /// Replaces the router, returning the previous one. In-flight matches
+    /// against the previous router are unaffected.
+    pub fn store(&self, router: CompiledRouter<T, K>) -> Arc<CompiledRouter<T, K>> {
+        let mut current = self.current.write().unwrap_or_else(|e| e.into_inner());
+        std::mem::replace(&mut *current, Arc::new(router))
+    }
+}
+
+impl<T, K> From<CompiledRouter<T, K>> for SharedRouter<T, K> {
+    fn from(router: CompiledRouter<T, K>) -> Self {
+        Self::new(router)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::router::Router;
+
+    fn assert_send_sync<S: Send + Sync>() {}
+
+    fn router(version: u32) -> CompiledRouter<u32> {
+        let mut router = Router::new();
+        router.add("/users/:id<number>".parse().unwrap(), version);
+        router.compile()
+    }
+
+    #[test]
+    fn routers_are_send_sync() {
+        assert_send_sync::<Router<u32>>();
+        assert_send_sync::<CompiledRouter<u32>>();
+        assert_send_sync::<SharedRouter<u32>>();
+    }
+    #[test]
+    fn shared_router_concurrent_matching() {
+        let shared = Arc::new(SharedRouter::new(router(1)));
+        let handles: Vec<_> = (0..8)
+            .map(|i| {
+                let shared = Arc::clone(&shared);
+                std::thread::spawn(move || {
+                    for _ in 0..100 {
+                        let router = shared.load();
+                        let matched = router.match_path(&format!("/users/{i}")).unwrap();
+                        assert!(*matched.value >= 1);
+                    }
+                })
+            })
+            .collect();
+        let previous = shared.store(router(2));
+        assert_eq!(*previous.match_path("/users/1").unwrap().value, 1);
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(*shared.load().match_path("/users/1").unwrap().value, 2);
+    }
+}
@@ -0,0 +1,131 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::errors::ParserConfigError;
+
+/// A regex-free single-segment template like `thumb-{id}.{ext}`. Literal
+/// parts must match exactly; each `{name}` captures a non-empty run of
+/// input up to the first occurrence of the literal that follows it (or to
+/// the end of the segment).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Template {
+    parts: Vec<TemplatePart>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TemplatePart {
+    Literal(String),
+    Capture(String),
+}
+
+impl Template {
+    pub fn parts(&self) -> &[TemplatePart] {
+        &self.parts
+    }
+    /// Names of the captures, in order.
+    pub fn capture_names(&self) -> impl Iterator<Item = &str> {
+        self.parts.iter().filter_map(|part| match part {
+            TemplatePart::Capture(name) => Some(name.as_str()),
+            TemplatePart::Literal(_) => None,
+        })
+    }
+    /// Returns `(name, value)` pairs if `input` fits the template.
+    pub fn captures<'i>(&self, input: &'i str) -> Option<Vec<(&str, &'i str)>> {
+        let mut rest = input;
+        let mut captures = Vec::new();
+        let mut parts = self.parts.iter().peekable();
+        while let Some(part) = parts.next() {
+            match part {
+                TemplatePart::Literal(literal) => rest = rest.strip_prefix(literal.as_str())?,
+                TemplatePart::Capture(name) => {
+                    let end = match parts.peek() {
+                        Some(TemplatePart::Literal(next)) => rest.find(next.as_str())?,
+                        _ => rest.len(),
+                    };
+                    if end == 0 {
+                        return None;
+                    }
+                    captures.push((name.as_str(), &rest[..end]));
+                    rest = &rest[end..];
+                }
+            }
+        }
+        rest.is_empty().then_some(captures)
+    }
+}
+
+impl FromStr for Template {
+    type Err = ParserConfigError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut parts = Vec::new();
+        let mut rest = s;
+        while !rest.is_empty() {
+            match rest.find('{') {
+                Some(0) => {
+                    let end = rest.find('}').ok_or(ParserConfigError::InvalidTemplate)?;
+                    let name = &rest[1..end];
+                    if name.is_empty()
+                        || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+                        || matches!(parts.last(), Some(TemplatePart::Capture(_)))
+                    {
+                        return Err(ParserConfigError::InvalidTemplate);
+                    }
+                    parts.push(TemplatePart::Capture(name.to_string()));
+                    rest = &rest[end + 1..];
+                }
+                found => {
+                    let end = found.unwrap_or(rest.len());
+                    let literal = &rest[..end];
+                    if literal.contains(['}', '/']) {
+                        return Err(ParserConfigError::InvalidTemplate);
+                    }
+                    parts.push(TemplatePart::Literal(literal.to_string()));
+                    rest = &rest[end..];
+                }
+            }
+        }
+        if parts.is_empty() {
+            return Err(ParserConfigError::InvalidTemplate);
+        }
+        Ok(Self { parts })
+    }
+}
+
+impl fmt::Display for Template {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for part in &self.parts {
+            match part {
+                TemplatePart::Literal(literal) => write!(f, "{literal}")?,
+                TemplatePart::Capture(name) => write!(f, "{{{name}}}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn template_captures_ok() {
+        let template: Template = "thumb-{id}.{ext}".parse().unwrap();
+        assert_eq!(
+            template.captures("thumb-42.jpg"),
+            Some(vec![("id", "42"), ("ext", "jpg")])
+        );
+    }
+    #[test]
+    fn template_captures_literal_mismatch() {
+        let template: Template = "thumb-{id}.{ext}".parse().unwrap();
+        assert_eq!(template.captures("photo-42.jpg"), None);
+        assert_eq!(template.captures("thumb-.jpg"), None);
+        assert_eq!(template.captures("thumb-42"), None);
+    }
+    #[test]
+    fn template_parse_err() {
+        assert!("{a}{b}".parse::<Template>().is_err());
+        assert!("thumb-{id".parse::<Template>().is_err());
+        assert!("a/{b}".parse::<Template>().is_err());
+    }
+}
@@ -0,0 +1,308 @@
+//! Approximate extended grapheme cluster counting, enough to treat emoji
+//! sequences (ZWJ families, skin tones, flags, keycaps) and common
+//! combining marks as single user-visible characters. This follows the
+//! UAX #29 rules but with hand-picked ranges rather than the full Unicode
+//! property tables, so rarely used scripts may count marks separately.
+//!
+//! Also approximate script detection, for `string(script=..)`.
+
+use std::str::FromStr;
+
+use crate::errors::ParserConfigError;
+
+/// The number of user-perceived characters in `s`.
+pub fn grapheme_count(s: &str) -> usize {
+    let mut count = 0;
+    let mut prev: Option<char> = None;
+    // regional indicators seen in a row, to pair them into flags
+    let mut regional_run = 0;
+    for c in s.chars() {
+        let joins = match prev {
+            None => false,
+            Some(p) => {
+                (p == '\r' && c == '\n')
+                    || is_extend(c)
+                    || (p == ZWJ && is_pictographic(c))
+                    || (is_regional(c) && is_regional(p) && regional_run % 2 == 1)
+                    || hangul_joins(p, c)
+            }
+        };
+        regional_run = if is_regional(c) { regional_run + 1 } else { 0 };
+        if !joins {
+            count += 1;
+        }
+        prev = Some(c);
+    }
+    count
+}
+
+const ZWJ: char = '\u{200D}';
+
+fn in_ranges(c: char, ranges: &[(u32, u32)]) -> bool {
+    let c = c as u32;
+    ranges.iter().any(|&(lo, hi)| (lo..=hi).contains(&c))
+}
+
+/// Combining marks, joiners and variation selectors that belong to no
+/// script of their own.
+fn is_inherited(c: char) -> bool {
+    in_ranges(
+        c,
+        &[
+            (0x0300, 0x036F),
+            (0x1AB0, 0x1AFF),
+            (0x1DC0, 0x1DFF),
+            (0x200C, 0x200D),
+            (0x20D0, 0x20FF),
+            (0xFE00, 0xFE0F),
+            (0xFE20, 0xFE2F),
+        ],
+    )
+}
+
+/// Combining marks, joiners, variation selectors, emoji modifiers and tags.
+fn is_extend(c: char) -> bool {
+    in_ranges(
+        c,
+        &[
+            (0x0300, 0x036F),
+            (0x0483, 0x0489),
+            (0x0591, 0x05BD),
+            (0x05BF, 0x05C7),
+            (0x0610, 0x061A),
+            (0x064B, 0x065F),
+            (0x0670, 0x0670),
+            (0x06D6, 0x06ED),
+            (0x0900, 0x0903),
+            (0x093A, 0x093C),
+            (0x093E, 0x094F),
+            (0x0951, 0x0957),
+            (0x0962, 0x0963),
+            (0x0E31, 0x0E31),
+            (0x0E34, 0x0E3A),
+            (0x0E47, 0x0E4E),
+            (0x1AB0, 0x1AFF),
+            (0x1DC0, 0x1DFF),
+            (0x200C, 0x200D),
+            (0x20D0, 0x20FF),
+            (0x3099, 0x309A),
+            (0xFE00, 0xFE0F),
+            (0xFE20, 0xFE2F),
+            (0x1F3FB, 0x1F3FF),
+            (0xE0020, 0xE007F),
+            (0xE0100, 0xE01EF),
+        ],
+    )
+}
+
+fn is_pictographic(c: char) -> bool {
+    in_ranges(
+        c,
+        &[
+            (0x00A9, 0x00A9),
+            (0x00AE, 0x00AE),
+            (0x203C, 0x2049),
+            (0x2122, 0x2139),
+            (0x2194, 0x21AA),
+            (0x2300, 0x23FF),
+            (0x24C2, 0x24C2),
+            (0x25AA, 0x27BF),
+            (0x2934, 0x2935),
+            (0x2B05, 0x2B55),
+            (0x3030, 0x303D),
+            (0x3297, 0x3299),
+            (0x1F000, 0x1F0FF),
+            (0x1F10D, 0x1F1AD),
+            (0x1F201, 0x1FAFF),
+        ],
+    )
+}
+
+fn is_regional(c: char) -> bool {
+    ('\u{1F1E6}'..='\u{1F1FF}').contains(&c)
+}
+
+#[derive(PartialEq)]
+enum Hangul {
+    L,
+    V,
+    T,
+    Lv,
+    Lvt,
+}
+
+fn hangul(c: char) -> Option<Hangul> {
+    match c as u32 {
+        0x1100..=0x115F | 0xA960..=0xA97C => Some(Hangul::L),
+        0x1160..=0x11A7 | 0xD7B0..=0xD7C6 => Some(Hangul::V),
+        0x11A8..=0x11FF | 0xD7CB..=0xD7FB => Some(Hangul::T),
+        s @ 0xAC00..=0xD7A3 if (s - 0xAC00) % 28 == 0 => Some(Hangul::Lv),
+        0xAC00..=0xD7A3 => Some(Hangul::Lvt),
+        _ => None,
+    }
+}
+
+/// Hangul jamo sequences that form one syllable block.
+fn hangul_joins(prev: char, c: char) -> bool {
+    use Hangul::*;
+    matches!(
+        (hangul(prev), hangul(c)),
+        (Some(L), Some(L | V | Lv | Lvt)) | (Some(Lv | V), Some(V | T)) | (Some(Lvt | T), Some(T))
+    )
+}
+
+/// Writing systems `string(script=..)` can restrict a segment to.
+///
+/// Letters that look alike across scripts (Latin `a` and Cyrillic `а`,
+/// Latin `o` and Greek `ο`) let an attacker register an identifier that
+/// renders the same as someone else's. Rejecting input that strays
+/// outside the expected scripts stops the mixed-script forms these
+/// homographs need, though not a lookalike spelled wholly in one script.
+/// Like the grapheme rules, scripts come from hand-picked block ranges
+/// rather than the Unicode tables.
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Copy, Clone)]
+pub enum Script {
+    Latin,
+    Greek,
+    Cyrillic,
+    Armenian,
+    Hebrew,
+    Arabic,
+    Devanagari,
+    Thai,
+    Hangul,
+    Hiragana,
+    Katakana,
+    Han,
+}
+
+impl Script {
+    const ALL: [Script; 12] = [
+        Script::Latin,
+        Script::Greek,
+        Script::Cyrillic,
+        Script::Armenian,
+        Script::Hebrew,
+        Script::Arabic,
+        Script::Devanagari,
+        Script::Thai,
+        Script::Hangul,
+        Script::Hiragana,
+        Script::Katakana,
+        Script::Han,
+    ];
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Script::Latin => "latin",
+            Script::Greek => "greek",
+            Script::Cyrillic => "cyrillic",
+            Script::Armenian => "armenian",
+            Script::Hebrew => "hebrew",
+            Script::Arabic => "arabic",
+            Script::Devanagari => "devanagari",
+            Script::Thai => "thai",
+            Script::Hangul => "hangul",
+            Script::Hiragana => "hiragana",
+            Script::Katakana => "katakana",
+            Script::Han => "han",
+        }
+    }
+    /// The script `c` belongs to, or `None` for characters shared by every
+    /// script, such as digits, punctuation and combining marks, and for
+    /// scripts not listed here.
+    pub fn of(c: char) -> Option<Script> {
+        Self::ALL
+            .into_iter()
+            .find(|script| in_ranges(c, script.ranges()))
+    }
+    /// Whether every char of `s` is from one of `scripts` or is shared by
+    /// all of them: ASCII digits and punctuation, and the combining marks
+    /// that take the script of the letter before them. Anything else from
+    /// a script `Script::of` doesn't know, like Cherokee or Latin small
+    /// capitals, is rejected rather than let through.
+    pub(crate) fn all_in(s: &str, scripts: &[Script]) -> bool {
+        s.chars().all(|c| match Script::of(c) {
+            Some(script) => scripts.contains(&script),
+            None => c.is_ascii_digit() || c.is_ascii_punctuation() || is_inherited(c),
+        })
+    }
+    fn ranges(&self) -> &'static [(u32, u32)] {
+        match self {
+            Script::Latin => &[
+                (0x0041, 0x005A),
+                (0x0061, 0x007A),
+                (0x00AA, 0x00AA),
+                (0x00BA, 0x00BA),
+                (0x00C0, 0x00D6),
+                (0x00D8, 0x00F6),
+                (0x00F8, 0x02AF),
+                (0x1E00, 0x1EFF),
+                (0x2C60, 0x2C7F),
+                (0xA720, 0xA7FF),
+                (0xFF21, 0xFF3A),
+                (0xFF41, 0xFF5A),
+            ],
+            Script::Greek => &[(0x0370, 0x03FF), (0x1F00, 0x1FFF)],
+            Script::Cyrillic => &[
+                (0x0400, 0x052F),
+                (0x1C80, 0x1C8F),
+                (0x2DE0, 0x2DFF),
+                (0xA640, 0xA69F),
+            ],
+            Script::Armenian => &[(0x0531, 0x058F)],
+            Script::Hebrew => &[(0x0591, 0x05FF)],
+            Script::Arabic => &[(0x0600, 0x06FF), (0x0750, 0x077F)],
+            Script::Devanagari => &[(0x0900, 0x097F)],
+            Script::Thai => &[(0x0E01, 0x0E7F)],
+            Script::Hangul => &[(0x1100, 0x11FF), (0x3131, 0x318F), (0xAC00, 0xD7AF)],
+            Script::Hiragana => &[(0x3041, 0x309F)],
+            Script::Katakana => &[(0x30A0, 0x30FF)],
+            Script::Han => &[(0x3400, 0x4DBF), (0x4E00, 0x9FFF), (0x20000, 0x2A6DF)],
+        }
+    }
+}
+
+impl FromStr for Script {
+    type Err = ParserConfigError;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|script| script.as_str() == s)
+            .ok_or(ParserConfigError::InvalidModifier)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn grapheme_count_emoji() {
+        // man, ZWJ, woman, ZWJ, girl
+        assert_eq!(
+            grapheme_count("\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}"),
+            1
+        );
+        // thumbs up with a skin tone
+        assert_eq!(grapheme_count("\u{1F44D}\u{1F3FD}"), 1);
+        // two flags
+        assert_eq!(grapheme_count("\u{1F1EF}\u{1F1F5}\u{1F1FA}\u{1F1F8}"), 2);
+        assert_eq!(grapheme_count("e\u{0301}te\u{0301}"), 3);
+        assert_eq!(grapheme_count("\u{1112}\u{1161}\u{11AB}"), 1);
+        assert_eq!(grapheme_count("abc"), 3);
+    }
+    #[test]
+    fn script_of_chars() {
+        assert_eq!(Script::of('a'), Some(Script::Latin));
+        assert_eq!(Script::of('é'), Some(Script::Latin));
+        // Cyrillic small a
+        assert_eq!(Script::of('\u{0430}'), Some(Script::Cyrillic));
+        assert_eq!(Script::of('\u{03BF}'), Some(Script::Greek));
+        assert_eq!(Script::of('中'), Some(Script::Han));
+        for common in ['7', '-', '_', '\u{0301}'] {
+            assert_eq!(Script::of(common), None, "{common:?}");
+        }
+        assert_eq!("cyrillic".parse::<Script>().ok(), Some(Script::Cyrillic));
+        assert!("klingon".parse::<Script>().is_err());
+    }
+}
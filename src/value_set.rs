@@ -0,0 +1,112 @@
+//! A prebuilt matcher for enums with many values. Values are compiled into
+//! the goto automaton of an Aho-Corasick machine, a byte trie; since enum
+//! values match whole segments, the failure links used for substring
+//! search are never needed and aren't built. A lookup costs one step per
+//! input byte however many values there are.
+
+/// Enums with at least this many values get a `ValueSet` when parsed.
+pub const VALUE_SET_THRESHOLD: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValueSet {
+    states: Vec<State>,
+    case_insensitive: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+struct State {
+    /// Sorted by byte.
+    edges: Vec<(u8, u32)>,
+    /// The index of the first value ending here.
+    value: Option<usize>,
+}
+
+impl ValueSet {
+    /// Compiles `values`. When `case_insensitive`, values and inputs are
+    /// compared by their lowercase forms.
+    pub fn new(values: &[String], case_insensitive: bool) -> Self {
+        let mut set = Self {
+            states: vec![State::default()],
+            case_insensitive,
+        };
+        for (index, value) in values.iter().enumerate() {
+            let mut state = 0;
+            for_each_byte(value, case_insensitive, |b| {
+                state = set_next(&mut set.states, state, b);
+                true
+            });
+            set.states[state].value.get_or_insert(index);
+        }
+        set
+    }
+    /// The index of the value `input` equals, if any.
+    pub fn find(&self, input: &str) -> Option<usize> {
+        let mut state = Some(0);
+        for_each_byte(input, self.case_insensitive, |b| {
+            state = state.and_then(|s| self.next(s, b));
+            state.is_some()
+        });
+        self.states[state?].value
+    }
+    fn next(&self, state: usize, b: u8) -> Option<usize> {
+        let edges = &self.states[state].edges;
+        let i = edges.binary_search_by_key(&b, |&(byte, _)| byte).ok()?;
+        Some(edges[i].1 as usize)
+    }
+}
+
+/// Feeds `s`'s bytes (lowercased if need be) to `f` until it returns `false`.
+fn for_each_byte(s: &str, case_insensitive: bool, mut f: impl FnMut(u8) -> bool) {
+    if !case_insensitive {
+        for b in s.bytes() {
+            if !f(b) {
+                return;
+            }
+        }
+        return;
+    }
+    let mut buf = [0; 4];
+    for c in s.chars().flat_map(char::to_lowercase) {
+        for &b in c.encode_utf8(&mut buf).as_bytes() {
+            if !f(b) {
+                return;
+            }
+        }
+    }
+}
+
+fn set_next(states: &mut Vec<State>, state: usize, b: u8) -> usize {
+    match states[state]
+        .edges
+        .binary_search_by_key(&b, |&(byte, _)| byte)
+    {
+        Ok(i) => states[state].edges[i].1 as usize,
+        Err(i) => {
+            let next = states.len();
+            states.push(State::default());
+            states[state].edges.insert(i, (b, next as u32));
+            next
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn value_set_large() {
+        let values: Vec<String> = (0..5000).map(|i| format!("sku-{i}")).collect();
+        let set = ValueSet::new(&values, false);
+        for (i, value) in values.iter().enumerate() {
+            assert_eq!(set.find(value), Some(i));
+        }
+        for miss in ["", "sku-", "sku-5000", "sku-12a", "SKU-1", "sku-0 "] {
+            assert_eq!(set.find(miss), None, "{miss}");
+        }
+        let set = ValueSet::new(&["Straße".to_string(), "ÉTÉ".to_string()], true);
+        assert_eq!(set.find("STRASSE"), None);
+        assert_eq!(set.find("straße"), Some(0));
+        assert_eq!(set.find("été"), Some(1));
+    }
+}
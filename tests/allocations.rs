@@ -0,0 +1,81 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+use urlpather::errors::MatchError;
+use urlpather::pattern::{MatchContext, Pattern};
+use urlpather::router::Router;
+
+struct CountingAllocator;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.with(|count| count.set(count.get() + 1));
+        System.alloc(layout)
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+fn allocations_during<R>(f: impl FnOnce() -> R) -> usize {
+    let before = ALLOCATIONS.with(Cell::get);
+    std::hint::black_box(f());
+    ALLOCATIONS.with(Cell::get) - before
+}
+
+#[test]
+fn leading_static_mismatch_does_not_allocate() {
+    let mut router = Router::new();
+    for section in ["users", "posts", "tags"] {
+        router.add(format!("/{section}/:id<number>").parse().unwrap(), section);
+    }
+    assert_eq!(allocations_during(|| router.match_path("/nope/5")), 0);
+    assert!(allocations_during(|| router.match_path("/tags/5")) > 0);
+}
+
+#[test]
+fn leading_static_mismatch_only_allocates_its_error() {
+    let pattern: Pattern = "/users/:id<number>/posts/:day<date>".parse().unwrap();
+    let mut results = Vec::new();
+    let error = || MatchError::StaticMismatch {
+        expected: "users".to_string(),
+        got: "nope".to_string(),
+    };
+    assert_eq!(
+        allocations_during(|| pattern.match_path_into("/nope/5", &mut results)),
+        allocations_during(error)
+    );
+    assert_eq!(results.capacity(), 0);
+}
+
+#[test]
+fn too_many_segments_rejected_before_matching() {
+    let pattern: Pattern = "/files/**path".parse().unwrap();
+    let ctx = MatchContext {
+        max_segments: Some(16),
+        ..Default::default()
+    };
+    let path = "/files".to_string() + &"/a".repeat(1_000_000);
+    assert_eq!(
+        allocations_during(|| pattern.match_path_with(&path, &ctx)),
+        0
+    );
+}
+
+#[test]
+fn match_path_into_skips_the_results_allocation() {
+    let pattern: Pattern = "/users/:id<number>/posts/:day<date>".parse().unwrap();
+    let path = "/users/5/posts/2021-01-01";
+    let mut results = Vec::new();
+    pattern.match_path_into(path, &mut results).unwrap();
+    let fresh = allocations_during(|| pattern.match_path(path));
+    let reused = allocations_during(|| pattern.match_path_into(path, &mut results));
+    assert_eq!(reused, fresh - 1);
+}
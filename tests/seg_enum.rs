@@ -0,0 +1,34 @@
+use urlpather::pattern::Pattern;
+use urlpather::seg_enum::SegEnum;
+use urlpather::segments::{MatchResult, Segment};
+
+#[derive(SegEnum, Debug, Clone, Copy, PartialEq)]
+#[seg_enum(rename_all = "kebab-case")]
+enum Sort {
+    Newest,
+    MostStarred,
+    #[seg_enum(rename = "a-z")]
+    Alphabetical,
+}
+
+#[test]
+fn derive_names_variants() {
+    assert_eq!(Sort::NAMES, ["newest", "most-starred", "a-z"]);
+    assert_eq!(Sort::from_name("a-z"), Some(Sort::Alphabetical));
+    assert_eq!(Sort::from_name("Newest"), None);
+}
+
+#[test]
+fn derived_var_matches_variants() {
+    let pattern = Pattern::new(vec![
+        Segment::Static("repos".to_string()),
+        Segment::Var(Sort::var("sort")),
+    ]);
+    let sort = |path: &str| match pattern.match_path(path).ok()?.pop()? {
+        MatchResult::Matched { value, .. } => Sort::from_value(&value),
+        _ => None,
+    };
+    assert_eq!(sort("/repos/most-starred"), Some(Sort::MostStarred));
+    assert_eq!(sort("/repos/a-z"), Some(Sort::Alphabetical));
+    assert_eq!(sort("/repos/MostStarred"), None);
+}
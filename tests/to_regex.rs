@@ -0,0 +1,362 @@
+//! Runs `Pattern::to_regex` output through a small backtracking matcher
+//! for the subset of regex syntax it emits. The matcher is strict about
+//! escapes, as JS's `u` flag is, so an escaped non-metacharacter fails
+//! to compile here too.
+
+use urlpather::pattern::Pattern;
+
+#[derive(Debug)]
+enum Node {
+    Char(char),
+    Class {
+        ranges: Vec<(char, char)>,
+        negated: bool,
+    },
+    Start,
+    End,
+    Group {
+        alternatives: Vec<Vec<Node>>,
+        name: Option<String>,
+        case_insensitive: bool,
+    },
+    Repeat {
+        node: Box<Node>,
+        min: usize,
+        max: Option<usize>,
+        lazy: bool,
+    },
+}
+
+struct Parser<'r> {
+    chars: std::iter::Peekable<std::str::Chars<'r>>,
+}
+
+impl Parser<'_> {
+    fn alternatives(&mut self) -> Result<Vec<Vec<Node>>, String> {
+        let mut alternatives = vec![Vec::new()];
+        while let Some(&c) = self.chars.peek() {
+            match c {
+                ')' => break,
+                '|' => {
+                    self.chars.next();
+                    alternatives.push(Vec::new());
+                }
+                _ => {
+                    let atom = self.atom()?;
+                    let node = self.quantified(atom)?;
+                    alternatives.last_mut().unwrap().push(node);
+                }
+            }
+        }
+        Ok(alternatives)
+    }
+    fn atom(&mut self) -> Result<Node, String> {
+        Ok(match self.chars.next().unwrap() {
+            '^' => Node::Start,
+            '$' => Node::End,
+            '(' => self.group()?,
+            '[' => self.class()?,
+            '\\' => match self.chars.next() {
+                Some('d') => Node::Class {
+                    ranges: vec![('0', '9')],
+                    negated: false,
+                },
+                Some(c) if r"\.+*?()|[]{}^$".contains(c) => Node::Char(c),
+                c => return Err(format!("invalid escape {c:?}")),
+            },
+            c @ ('*' | '+' | '?' | '{' | ')' | ']' | '}') => {
+                return Err(format!("unexpected {c:?}"))
+            }
+            c => Node::Char(c),
+        })
+    }
+    fn group(&mut self) -> Result<Node, String> {
+        let mut name = None;
+        let mut case_insensitive = false;
+        if self.chars.next_if_eq(&'?').is_some() {
+            match self.chars.next() {
+                Some(':') => {}
+                Some('i') if self.chars.next_if_eq(&':').is_some() => case_insensitive = true,
+                Some('P') if self.chars.next_if_eq(&'<').is_some() => {
+                    let group_name: String =
+                        self.chars.by_ref().take_while(|&c| c != '>').collect();
+                    name = Some(group_name);
+                }
+                c => return Err(format!("unsupported group flag {c:?}")),
+            }
+        }
+        let alternatives = self.alternatives()?;
+        self.chars.next_if_eq(&')').ok_or("unclosed group")?;
+        Ok(Node::Group {
+            alternatives,
+            name,
+            case_insensitive,
+        })
+    }
+    fn class(&mut self) -> Result<Node, String> {
+        let negated = self.chars.next_if_eq(&'^').is_some();
+        let mut ranges = Vec::new();
+        loop {
+            let c = match self.chars.next().ok_or("unclosed class")? {
+                ']' => break,
+                '\\' => self.chars.next().ok_or("unclosed class")?,
+                c => c,
+            };
+            let mut lookahead = self.chars.clone();
+            match (lookahead.next(), lookahead.next()) {
+                (Some('-'), Some(end)) if end != ']' => {
+                    self.chars.next();
+                    self.chars.next();
+                    ranges.push((c, end));
+                }
+                _ => ranges.push((c, c)),
+            }
+        }
+        Ok(Node::Class { ranges, negated })
+    }
+    fn quantified(&mut self, node: Node) -> Result<Node, String> {
+        let (min, max) = match self.chars.peek() {
+            Some('?') => (0, Some(1)),
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('{') => {
+                self.chars.next();
+                let body: String = self.chars.by_ref().take_while(|&c| c != '}').collect();
+                let number = |s: &str| {
+                    s.parse::<usize>()
+                        .map_err(|_| format!("bad count {body:?}"))
+                };
+                let bounds = match body.split_once(',') {
+                    Some((min, "")) => (number(min)?, None),
+                    Some((min, max)) => (number(min)?, Some(number(max)?)),
+                    None => (number(&body)?, Some(number(&body)?)),
+                };
+                let lazy = self.chars.next_if_eq(&'?').is_some();
+                return Ok(repeat(node, bounds, lazy));
+            }
+            _ => return Ok(node),
+        };
+        self.chars.next();
+        let lazy = self.chars.next_if_eq(&'?').is_some();
+        Ok(repeat(node, (min, max), lazy))
+    }
+}
+
+fn repeat(node: Node, (min, max): (usize, Option<usize>), lazy: bool) -> Node {
+    Node::Repeat {
+        node: Box::new(node),
+        min,
+        max,
+        lazy,
+    }
+}
+
+type Captures = Vec<(String, usize, usize)>;
+
+/// Every way `nodes` can match from `pos`, in the order a backtracking
+/// engine would try them.
+fn sequence(
+    nodes: &[Node],
+    text: &[char],
+    pos: usize,
+    ci: bool,
+    caps: Captures,
+) -> Vec<(usize, Captures)> {
+    let Some((first, rest)) = nodes.split_first() else {
+        return vec![(pos, caps)];
+    };
+    let mut out = Vec::new();
+    for (end, caps) in node(first, text, pos, ci, caps) {
+        out.extend(sequence(rest, text, end, ci, caps));
+    }
+    out
+}
+
+fn node(
+    node: &Node,
+    text: &[char],
+    pos: usize,
+    ci: bool,
+    caps: Captures,
+) -> Vec<(usize, Captures)> {
+    let fits = |matched: bool| {
+        if matched {
+            vec![(pos + 1, caps.clone())]
+        } else {
+            Vec::new()
+        }
+    };
+    match node {
+        Node::Char(c) => fits(
+            text.get(pos)
+                .is_some_and(|t| t == c || ci && t.to_lowercase().eq(c.to_lowercase())),
+        ),
+        Node::Class { ranges, negated } => fits(
+            text.get(pos)
+                .is_some_and(|t| ranges.iter().any(|&(lo, hi)| (lo..=hi).contains(t)) != *negated),
+        ),
+        Node::Start if pos == 0 => vec![(pos, caps)],
+        Node::End if pos == text.len() => vec![(pos, caps)],
+        Node::Start | Node::End => Vec::new(),
+        Node::Group {
+            alternatives,
+            name,
+            case_insensitive,
+        } => {
+            let mut out = Vec::new();
+            for alternative in alternatives {
+                for (end, mut caps) in sequence(
+                    alternative,
+                    text,
+                    pos,
+                    ci || *case_insensitive,
+                    caps.clone(),
+                ) {
+                    if let Some(name) = name {
+                        caps.push((name.clone(), pos, end));
+                    }
+                    out.push((end, caps));
+                }
+            }
+            out
+        }
+        Node::Repeat { .. } => repetitions(node, text, pos, ci, caps, 0),
+    }
+}
+
+fn repetitions(
+    repeat: &Node,
+    text: &[char],
+    pos: usize,
+    ci: bool,
+    caps: Captures,
+    count: usize,
+) -> Vec<(usize, Captures)> {
+    let Node::Repeat {
+        node: inner,
+        min,
+        max,
+        lazy,
+    } = repeat
+    else {
+        unreachable!()
+    };
+    let mut more = Vec::new();
+    if max.is_none_or(|max| count < max) {
+        for (end, caps) in node(inner, text, pos, ci, caps.clone()) {
+            if end > pos {
+                more.extend(repetitions(repeat, text, end, ci, caps, count + 1));
+            }
+        }
+    }
+    let stop = if count >= *min {
+        vec![(pos, caps)]
+    } else {
+        Vec::new()
+    };
+    if *lazy {
+        stop.into_iter().chain(more).collect()
+    } else {
+        more.into_iter().chain(stop).collect()
+    }
+}
+
+/// Compiles `regex` and matches it against `text`, returning the named
+/// groups of the first match.
+fn regex_captures(regex: &str, text: &str) -> Option<Vec<(String, String)>> {
+    let mut parser = Parser {
+        chars: regex.chars().peekable(),
+    };
+    let alternatives = parser
+        .alternatives()
+        .unwrap_or_else(|e| panic!("{regex}: {e}"));
+    assert!(parser.chars.next().is_none(), "{regex}: unbalanced )");
+    let text: Vec<char> = text.chars().collect();
+    let root = Node::Group {
+        alternatives,
+        name: None,
+        case_insensitive: false,
+    };
+    // a leading `^` anchors the regex, so trying only position 0 is enough
+    let (_, caps) = node(&root, &text, 0, false, Vec::new())
+        .into_iter()
+        .next()?;
+    let mut named: Vec<(String, String)> = caps
+        .into_iter()
+        .map(|(name, start, end)| (name, text[start..end].iter().collect()))
+        .collect();
+    named.sort();
+    Some(named)
+}
+
+fn captures(pairs: &[(&str, &str)]) -> Option<Vec<(String, String)>> {
+    let mut pairs: Vec<_> = pairs
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+    pairs.sort();
+    Some(pairs)
+}
+
+#[test]
+fn to_regex_matches_mixed_pattern() {
+    let pattern: Pattern = "/users/:id<integer>/posts/:day<date>/:sort<enum.i(asc,desc)>/"
+        .parse()
+        .unwrap();
+    let regex = pattern.to_regex().unwrap();
+    let path = "/users/42/posts/2021-01-02/DESC/";
+    assert!(pattern.match_path(path).is_ok());
+    assert_eq!(
+        regex_captures(&regex, path),
+        captures(&[("day", "2021-01-02"), ("id", "42"), ("sort", "DESC")])
+    );
+    for path in [
+        "/users/x/posts/2021-01-02/asc/",
+        "/users/1/posts/2021-01-02/up/",
+    ] {
+        assert!(pattern.match_path(path).is_err());
+        assert_eq!(regex_captures(&regex, path), None, "{path}");
+    }
+}
+
+#[test]
+fn to_regex_matches_escaped_literals() {
+    let pattern: Pattern = "/v1.0/:img<template(thumb-{id}.{ext})>/:w<number(unit=px)>?"
+        .parse()
+        .unwrap();
+    let regex = pattern.to_regex().unwrap();
+    assert_eq!(
+        regex_captures(&regex, "/v1.0/thumb-7.png/300px"),
+        captures(&[
+            ("ext", "png"),
+            ("id", "7"),
+            ("img", "thumb-7.png"),
+            ("w", "300")
+        ])
+    );
+    assert_eq!(
+        regex_captures(&regex, "/v1.0/thumb-7.png"),
+        captures(&[("ext", "png"), ("id", "7"), ("img", "thumb-7.png")])
+    );
+    // `.` is escaped, so it doesn't match any char
+    assert_eq!(regex_captures(&regex, "/v1x0/thumb-7.png"), None);
+
+    let pattern: Pattern = "/css/*.min.css".parse().unwrap();
+    let regex = pattern.to_regex().unwrap();
+    assert_eq!(regex_captures(&regex, "/css/site.min.css"), captures(&[]));
+    assert_eq!(regex_captures(&regex, "/css/a/site.min.css"), None);
+    assert_eq!(regex_captures(&regex, "/css/site-min.css"), None);
+}
+
+#[test]
+fn to_regex_matches_alternatives_and_separators() {
+    let pattern: Pattern = "/a/:a<integer|date>/:n<integer(sep=comma)>"
+        .parse()
+        .unwrap();
+    let regex = pattern.to_regex().unwrap();
+    assert_eq!(
+        regex_captures(&regex, "/a/2021-01-02/1,000"),
+        captures(&[("a", "2021-01-02"), ("n", "1,000")])
+    );
+    assert_eq!(regex_captures(&regex, "/a/7/10,00"), None);
+}